@@ -11,6 +11,15 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+#![allow(
+    clippy::assign_op_pattern,
+    clippy::bool_comparison,
+    clippy::len_zero,
+    clippy::needless_range_loop,
+    clippy::single_match,
+    clippy::unnecessary_cast,
+)]
+
 extern crate rand;
 use rand::Rng;
 
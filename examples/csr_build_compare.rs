@@ -0,0 +1,47 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::{BuildOptions, CSR};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/*Time each way of placing edges on the same binary edge list*/
+fn main() {
+    const NUMV: usize = 500000;
+    let ways = [
+        ("atomic", BuildOptions::default()),
+        (
+            "deterministic",
+            BuildOptions {
+                deterministic: true,
+                ..Default::default()
+            },
+        ),
+        (
+            "propagation blocking",
+            BuildOptions {
+                propagation_blocking: true,
+                ..Default::default()
+            },
+        ),
+    ];
+
+    for (name, opts) in ways.iter() {
+        let start = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
+        let csr = CSR::new_from_el_mmap_with(NUMV, String::from("graphs/large.el"), opts).unwrap();
+        let end = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
+
+        println!("{} CSR Build Time: {}ms", name, end - start);
+        println!("({}ns / vtx)", (end - start) as f64 * 1e6 / csr.get_v() as f64);
+    }
+}
@@ -0,0 +1,10 @@
+extern crate raphy;
+use raphy::external::ExternalBuilder;
+
+fn main() {
+    let h = ExternalBuilder::new()
+        .chunk_edges(1 << 20)
+        .build("graphs/large.el", "graphs/large.csr")
+        .unwrap();
+    println!("{} vertices, {} edges", h.v, h.e);
+}
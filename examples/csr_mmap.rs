@@ -4,6 +4,6 @@ extern crate raphy;
 use raphy::csr::CSR;
 
 fn main() {
-    let csr = CSR::new_from_el_mmap(500000,String::from("graphs/large.el"));
-    csr.write_fastcsr(String::from("graphs/large.csr"));
+    let csr = CSR::new_from_el_mmap(500000,String::from("graphs/large.el")).unwrap();
+    csr.write_fastcsr(String::from("graphs/large.csr")).unwrap();
 }
@@ -11,6 +11,8 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+#![allow(clippy::assign_op_pattern)]
+
 extern crate rand;
 extern crate raphy;
 use raphy::csr::CSR;
@@ -21,7 +23,7 @@ fn main() {
     const NUMV: usize = 500000; 
     let start = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
 
-    let mut csr = CSR::new_from_el_mmap(NUMV,String::from("graphs/large.el"));
+    let mut csr = CSR::new_from_el_mmap(NUMV,String::from("graphs/large.el")).unwrap();
 
     let setup = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
 
@@ -1,3 +1,8 @@
+#![allow(
+    clippy::assign_op_pattern,
+    clippy::same_item_push,
+)]
+
 extern crate rand;
 
 extern crate raphy;
@@ -9,7 +14,7 @@ fn main() {
 
     const NUMITERS: usize = 10;
     let start = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
-    let fcsr = FastCSR::new(String::from("graphs/large.csr"));
+    let fcsr = FastCSR::new(String::from("graphs/large.csr")).unwrap();
     let setup = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
 
     let mut vp1 = Vec::with_capacity(fcsr.getv());
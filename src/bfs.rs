@@ -0,0 +1,307 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  Parallel level-synchronous breadth first search.  Each level expands
+  every vertex of the frontier in parallel; a neighbor joins the next
+  frontier if its bit in the visited set was not yet set, and the thread
+  that sets the bit records the neighbor's parent and depth.  The frontier
+  and visited sets are bit vectors with atomic words, so a level costs a
+  scan of v/64 words plus the edges of the frontier.
+
+  The direction-optimizing search (Beamer, Asanovic, and Patterson, SC'12)
+  also has a bottom-up step: every unvisited vertex looks through its
+  in-neighbors for one in the frontier, stopping at the first it finds.
+  When the frontier is large that checks far fewer edges than expanding
+  it.  The search goes bottom-up once the frontier's out-edges outnumber
+  the out-edges of the unvisited vertices divided by alpha, and back to
+  top-down once the frontier is shrinking and has fewer than v / beta
+  vertices.
+*/
+
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::index::Idx;
+use crate::traverse::Adjacency;
+
+/// The depth and parent of a vertex the search did not reach
+pub const UNREACHED: usize = usize::MAX;
+
+/// The result of a breadth first search: for each vertex, its distance in
+/// edges from the start vertex, and the vertex it was discovered from.
+/// The start vertex is its own parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BfsTree {
+    pub depth: Vec<usize>,
+    pub parent: Vec<usize>,
+}
+
+impl BfsTree {
+    /// True if the search reached vertex v
+    pub fn reached(&self, v: usize) -> bool {
+        self.depth[v] != UNREACHED
+    }
+
+    /// The (parent,child) edges of the tree, e.g., to highlight with
+    /// export::ExportOptions
+    pub fn tree_edges(&self) -> Vec<(usize, usize)> {
+        self.parent
+            .iter()
+            .enumerate()
+            .filter(|(v, p)| **p != UNREACHED && **p != *v)
+            .map(|(v, p)| (*p, v))
+            .collect()
+    }
+}
+
+/*A fixed size bit vector that threads can set bits of concurrently*/
+struct Bitmap {
+    words: Vec<AtomicU64>,
+}
+
+impl Bitmap {
+    fn new(n: usize) -> Bitmap {
+        Bitmap {
+            words: (0..n.div_ceil(64)).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.words[i / 64].load(Ordering::Relaxed) & (1 << (i % 64)) != 0
+    }
+
+    /*Set bit i, returning true if this call is the one that set it*/
+    fn set(&self, i: usize) -> bool {
+        let bit = 1 << (i % 64);
+        self.words[i / 64].fetch_or(bit, Ordering::Relaxed) & bit == 0
+    }
+
+    fn clear(&mut self) {
+        self.words.par_iter_mut().for_each(|w| *w.get_mut() = 0);
+    }
+
+    /*Call f with every set bit, in parallel across words*/
+    fn par_for_each(&self, f: impl Fn(usize) + Sync) {
+        self.words.par_iter().enumerate().for_each(|(i, w)| {
+            let mut bits = w.load(Ordering::Relaxed);
+            while bits != 0 {
+                f(i * 64 + bits.trailing_zeros() as usize);
+                bits &= bits - 1;
+            }
+        });
+    }
+}
+
+/*The depth and parent arrays as the search fills them in, in parallel*/
+struct Search {
+    depth: Vec<AtomicUsize>,
+    parent: Vec<AtomicUsize>,
+    visited: Bitmap,
+}
+
+impl Search {
+    fn new(v: usize, start: usize) -> Search {
+        assert!(start < v, "start vertex {} of a graph with {} vertices", start, v);
+        let s = Search {
+            depth: (0..v).map(|_| AtomicUsize::new(UNREACHED)).collect(),
+            parent: (0..v).map(|_| AtomicUsize::new(UNREACHED)).collect(),
+            visited: Bitmap::new(v),
+        };
+        s.visit(start, start, 0);
+        s
+    }
+
+    /*Record that n was reached from v at depth d*/
+    fn visit(&self, n: usize, v: usize, d: usize) {
+        self.parent[n].store(v, Ordering::Relaxed);
+        self.depth[n].store(d, Ordering::Relaxed);
+    }
+
+    fn finish(self) -> BfsTree {
+        BfsTree {
+            depth: self.depth.into_iter().map(AtomicUsize::into_inner).collect(),
+            parent: self.parent.into_iter().map(AtomicUsize::into_inner).collect(),
+        }
+    }
+}
+
+/*The size of a frontier: its vertices and their out-edges*/
+#[derive(Debug, Clone, Copy, Default)]
+struct Found {
+    vertices: usize,
+    edges: usize,
+}
+
+impl Found {
+    fn add(&self, vertices: &AtomicUsize, edges: &AtomicUsize) {
+        vertices.fetch_add(self.vertices, Ordering::Relaxed);
+        edges.fetch_add(self.edges, Ordering::Relaxed);
+    }
+}
+
+/*Expand every vertex of frontier at depth d, setting the bits of the
+vertices it discovers in next.  Returns the size of next.*/
+fn top_down_step<G: Adjacency + ?Sized>(g: &G, s: &Search, frontier: &Bitmap, next: &Bitmap, d: usize) -> Found {
+    let (vertices, edges) = (AtomicUsize::new(0), AtomicUsize::new(0));
+    frontier.par_for_each(|v| {
+        let mut mine = Found::default();
+        for n in g.out_neighbors(v) {
+            let n = n.index();
+            if !s.visited.get(n) && s.visited.set(n) {
+                s.visit(n, v, d + 1);
+                next.set(n);
+                mine.vertices += 1;
+                mine.edges += g.out_neighbors(n).len();
+            }
+        }
+        mine.add(&vertices, &edges);
+    });
+    Found {
+        vertices: vertices.into_inner(),
+        edges: edges.into_inner(),
+    }
+}
+
+/*Have every unvisited vertex look for an in-neighbor in frontier at depth
+d, setting its bit in next if it finds one.  Returns the size of next.*/
+fn bottom_up_step<G: Adjacency + ?Sized>(g: &G, s: &Search, frontier: &Bitmap, next: &Bitmap, d: usize) -> Found {
+    let (vertices, edges) = (AtomicUsize::new(0), AtomicUsize::new(0));
+    (0..g.num_v()).into_par_iter().for_each(|v| {
+        if s.visited.get(v) {
+            return;
+        }
+        if let Some(p) = g.in_neighbors(v).iter().find(|p| frontier.get(p.index())) {
+            s.visited.set(v);
+            s.visit(v, p.index(), d + 1);
+            next.set(v);
+            let mine = Found {
+                vertices: 1,
+                edges: g.out_neighbors(v).len(),
+            };
+            mine.add(&vertices, &edges);
+        }
+    });
+    Found {
+        vertices: vertices.into_inner(),
+        edges: edges.into_inner(),
+    }
+}
+
+/// Breadth first search of g from vertex start, one level at a time with
+/// each level's frontier expanded in parallel.  The depths are the same on
+/// every run; when a vertex can be discovered from several vertices of the
+/// previous level, which of them becomes its parent is not.  Panics if
+/// start is not a vertex of g.
+pub fn bfs<G: Adjacency + ?Sized>(g: &G, start: usize) -> BfsTree {
+    let v = g.num_v();
+    let s = Search::new(v, start);
+    let mut frontier = Bitmap::new(v);
+    let mut next = Bitmap::new(v);
+    frontier.set(start);
+    s.visited.set(start);
+
+    let mut d = 0;
+    while top_down_step(g, &s, &frontier, &next, d).vertices > 0 {
+        std::mem::swap(&mut frontier, &mut next);
+        next.clear();
+        d += 1;
+    }
+    s.finish()
+}
+
+/// Which way a level of a direction-optimizing search was expanded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The frontier's vertices pushed to their out-neighbors
+    TopDown,
+    /// The unvisited vertices pulled from their in-neighbors
+    BottomUp,
+}
+
+/// The thresholds for switching directions.  The defaults are the values
+/// Beamer et al. found to work well across graphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirectionOptions {
+    /// Go bottom-up when the frontier's out-edges exceed the unvisited
+    /// vertices' out-edges divided by alpha.  A larger alpha switches
+    /// sooner.
+    pub alpha: usize,
+    /// Go back to top-down when a shrinking frontier has fewer than v / beta
+    /// vertices.  A larger beta stays bottom-up longer.
+    pub beta: usize,
+}
+
+impl Default for DirectionOptions {
+    fn default() -> DirectionOptions {
+        DirectionOptions { alpha: 15, beta: 18 }
+    }
+}
+
+/// Direction-optimizing breadth first search of g from vertex start,
+/// switching between top-down and bottom-up levels as opts says.  Returns
+/// the same depths as bfs, and the direction each level was expanded in:
+/// directions[d] is how the vertices at depth d + 1 were found.  Panics if
+/// start is not a vertex of g or g does not keep its in-edges.
+pub fn direction_optimizing_bfs<G: Adjacency + ?Sized>(
+    g: &G,
+    start: usize,
+    opts: &DirectionOptions,
+) -> (BfsTree, Vec<Direction>) {
+    assert!(
+        g.has_in_edges(),
+        "direction-optimizing BFS needs in-edges, build them with with_in_edges"
+    );
+    let v = g.num_v();
+    let s = Search::new(v, start);
+    let mut frontier = Bitmap::new(v);
+    let mut next = Bitmap::new(v);
+    frontier.set(start);
+    s.visited.set(start);
+
+    /*Out-edges of the frontier and of the vertices not yet visited*/
+    let total: usize = (0..v).into_par_iter().map(|u| g.out_neighbors(u).len()).sum();
+    let mut size = Found {
+        vertices: 1,
+        edges: g.out_neighbors(start).len(),
+    };
+    let mut unexplored = total - size.edges;
+
+    let mut directions = Vec::new();
+    let mut dir = Direction::TopDown;
+    let mut prev = 0;
+    loop {
+        dir = match dir {
+            Direction::TopDown if size.edges > unexplored / opts.alpha.max(1) => Direction::BottomUp,
+            Direction::BottomUp if size.vertices < prev && size.vertices < v / opts.beta.max(1) => {
+                Direction::TopDown
+            }
+            dir => dir,
+        };
+        let d = directions.len();
+        let found = match dir {
+            Direction::TopDown => top_down_step(g, &s, &frontier, &next, d),
+            Direction::BottomUp => bottom_up_step(g, &s, &frontier, &next, d),
+        };
+        if found.vertices == 0 {
+            break;
+        }
+        directions.push(dir);
+        std::mem::swap(&mut frontier, &mut next);
+        next.clear();
+        unexplored -= found.edges;
+        prev = size.vertices;
+        size = found;
+    }
+    (s.finish(), directions)
+}
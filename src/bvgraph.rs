@@ -0,0 +1,420 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  Reference-compressed graphs in the style of WebGraph's BVGraph.  In a web
+  crawl, pages near each other in id order tend to link to nearly the same
+  pages, so a vertex's list is best coded as a copy of most of a nearby
+  list plus whatever is left over.  Each vertex's sorted, duplicate-free
+  neighbor list is a run of varints:
+
+  |degree|reference|copy blocks|intervals|residuals|
+
+  reference  r: 0 for none, else the list of vertex x - r is copied from,
+             r at most the window.
+  copy blocks (only if r > 0): a count, then the lengths of alternating runs
+             of the referenced list to copy and to skip, starting with a copy
+             run.  Anything after the last block is skipped.
+  intervals: a count, then for each run of at least min_interval consecutive
+             ids left over after copying, its first id (zigzag from x for the
+             first interval, else its distance past the end of the previous
+             one, less 2) and its length.
+  residuals: the rest, as many as the degree leaves: the first zigzag coded
+             from x, the others as their gap from the previous one, less 1.
+
+  Decoding a list decodes the list it references first, so the writer
+  bounds how long such chains can get.  The file is a compressed FastCSR
+  file (see compressed.rs) with FLAG_REFERENCES also set.
+*/
+
+use rayon::prelude::*;
+use std::path::Path;
+
+use crate::compressed::{self, get_varint, put_varint, unzigzag, zigzag, Encoded};
+use crate::csr::CSR;
+use crate::error::{Error, FormatError, Result};
+use crate::fast_csr::{FastCSR, Validation};
+use crate::format::{self, Header};
+use crate::index::Idx;
+use crate::weight::Weight;
+use crate::PropTrait;
+
+/// The longest chain of references a list can be decoded through
+pub const MAX_CHAIN: usize = 64;
+
+/// How hard the writer looks for lists to copy from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BvOptions {
+    /// How many of the preceding vertices' lists each list may copy from
+    pub window: usize,
+    /// The most references decoding one list may have to follow, at most
+    /// MAX_CHAIN.  Longer chains compress better and decode slower; 0 turns
+    /// copying off.
+    pub max_ref_chain: usize,
+    /// The shortest run of consecutive ids coded as an interval
+    pub min_interval: usize,
+}
+
+impl Default for BvOptions {
+    fn default() -> Self {
+        BvOptions {
+            window: 7,
+            max_ref_chain: 3,
+            min_interval: 4,
+        }
+    }
+}
+
+/*Append the encoding of vertex x's sorted list, copying from reflist (the
+list of x - r) if r > 0*/
+fn encode_list(x: usize, list: &[usize], r: usize, reflist: &[usize], min_interval: usize, out: &mut Vec<u8>) {
+    put_varint(out, list.len() as u64);
+    if list.is_empty() {
+        return;
+    }
+    put_varint(out, r as u64);
+
+    let mut extras = Vec::with_capacity(list.len());
+    if r > 0 {
+        /*Mark which of the referenced list's ids this list has, in runs*/
+        let mut blocks = Vec::new();
+        let (mut copying, mut run, mut j) = (true, 0, 0);
+        for y in reflist {
+            while j < list.len() && list[j] < *y {
+                extras.push(list[j]);
+                j += 1;
+            }
+            let has = j < list.len() && list[j] == *y;
+            if has {
+                j += 1;
+            }
+            if has != copying {
+                blocks.push(run);
+                run = 0;
+                copying = has;
+            }
+            run += 1;
+        }
+        if copying {
+            blocks.push(run);
+        }
+        extras.extend_from_slice(&list[j..]);
+
+        put_varint(out, blocks.len() as u64);
+        for b in blocks {
+            put_varint(out, b as u64);
+        }
+    } else {
+        extras.extend_from_slice(list);
+    }
+
+    /*Split what is left into intervals and residuals*/
+    let mut intervals = Vec::new();
+    let mut residuals = Vec::new();
+    let mut i = 0;
+    while i < extras.len() {
+        let mut k = i + 1;
+        while k < extras.len() && extras[k] == extras[k - 1] + 1 {
+            k += 1;
+        }
+        match k - i >= min_interval.max(1) {
+            true => intervals.push((extras[i], k - i)),
+            false => residuals.extend_from_slice(&extras[i..k]),
+        }
+        i = k;
+    }
+
+    put_varint(out, intervals.len() as u64);
+    let mut prev_end = 0;
+    for (k, (left, len)) in intervals.iter().enumerate() {
+        match k {
+            0 => put_varint(out, zigzag(*left as i64 - x as i64)),
+            _ => put_varint(out, (left - prev_end - 2) as u64),
+        }
+        put_varint(out, *len as u64);
+        prev_end = left + len - 1;
+    }
+    for (k, n) in residuals.iter().enumerate() {
+        match k {
+            0 => put_varint(out, zigzag(*n as i64 - x as i64)),
+            _ => put_varint(out, (n - residuals[k - 1] - 1) as u64),
+        }
+    }
+}
+
+/*Write a BvGraph file at path for a graph of v vertices, where list(u, buf)
+appends vertex u's neighbors to buf, marked directed if directed is true.
+Blocks of vertices are encoded in parallel, each list trying every list in
+its window (within its block) as a reference and keeping the shortest
+encoding.*/
+fn write<Q: AsRef<Path>>(
+    path: Q,
+    v: usize,
+    directed: bool,
+    list: impl Fn(usize, &mut Vec<usize>) + Sync,
+    opts: &BvOptions,
+) -> Result<Header> {
+    let mut flags = format::FLAG_SORTED | format::FLAG_REFERENCES;
+    if directed {
+        flags |= format::FLAG_DIRECTED;
+    }
+    let max_chain = opts.max_ref_chain.min(MAX_CHAIN);
+    compressed::write_blocks(path, v, flags, |lo, hi, bytes, starts| {
+        let mut lists: Vec<Vec<usize>> = Vec::with_capacity(hi - lo);
+        let mut chain: Vec<usize> = Vec::with_capacity(hi - lo);
+        let (mut best, mut scratch) = (Vec::new(), Vec::new());
+        let mut ne = 0;
+        for x in lo..hi {
+            let mut l = Vec::new();
+            list(x, &mut l);
+            l.sort_unstable();
+            l.dedup();
+            ne += l.len();
+
+            best.clear();
+            encode_list(x, &l, 0, &[], opts.min_interval, &mut best);
+            let mut best_chain = 0;
+            for r in 1..=opts.window.min(x - lo) {
+                let y = x - lo - r;
+                if chain[y] + 1 > max_chain || l.is_empty() {
+                    continue;
+                }
+                scratch.clear();
+                encode_list(x, &l, r, &lists[y], opts.min_interval, &mut scratch);
+                if scratch.len() < best.len() {
+                    std::mem::swap(&mut best, &mut scratch);
+                    best_chain = chain[y] + 1;
+                }
+            }
+
+            starts.push(bytes.len());
+            bytes.extend_from_slice(&best);
+            lists.push(l);
+            chain.push(best_chain);
+
+            /*Lists that have left the window are not needed again*/
+            if x - lo >= opts.window {
+                lists[x - lo - opts.window] = Vec::new();
+            }
+        }
+        ne
+    })
+}
+
+/// A mapped, reference-compressed graph (see bvgraph.rs for the encoding).
+/// Any vertex's neighbors can be decoded on their own, following at most
+/// max_ref_chain references to earlier lists.  Repeated edges are stored
+/// once, and weights and in-edges are not stored.
+pub struct BvGraph {
+    enc: Encoded,
+}
+
+impl BvGraph {
+    /// Compress csr into a BvGraph file at path and open it
+    pub fn from_csr<P: PropTrait, W: Weight, I: Idx, O: Idx, Q: AsRef<Path>>(
+        csr: &CSR<P, W, I, O>,
+        path: Q,
+        opts: &BvOptions,
+    ) -> Result<BvGraph> {
+        let list = |u: usize, buf: &mut Vec<usize>| {
+            let (st, en) = csr.vtx_offset_range(u);
+            buf.extend(csr.get_neighbs()[st..en].iter().map(|n| n.index()));
+        };
+        write(path.as_ref(), csr.get_v(), !csr.is_symmetric(), list, opts)?;
+        BvGraph::open_with(path, Validation::Header)
+    }
+
+    /// Compress fcsr into a BvGraph file at path and open it
+    pub fn from_fastcsr<I: Idx, O: Idx, Q: AsRef<Path>>(
+        fcsr: &FastCSR<I, O>,
+        path: Q,
+        opts: &BvOptions,
+    ) -> Result<BvGraph> {
+        let list = |u: usize, buf: &mut Vec<usize>| {
+            buf.extend(fcsr.neighbors(u).iter().map(|n| n.index()));
+        };
+        write(path.as_ref(), fcsr.getv(), fcsr.is_directed(), list, opts)?;
+        BvGraph::open_with(path, Validation::Header)
+    }
+
+    /// Load a BvGraph file, decoding every list (in parallel) to check it
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<BvGraph> {
+        BvGraph::open_with(path, Validation::Parallel)
+    }
+
+    /// Load a BvGraph file, checking as much of it as the validation level
+    /// asks for
+    pub fn open_with<P: AsRef<Path>>(path: P, check: Validation) -> Result<BvGraph> {
+        let g = BvGraph {
+            enc: Encoded::open(path.as_ref(), true)?,
+        };
+
+        match check {
+            Validation::Header => (),
+            Validation::Sequential => g.validate(false)?,
+            Validation::Parallel => g.validate(true)?,
+        }
+
+        Ok(g)
+    }
+
+    pub fn getv(&self) -> usize {
+        self.enc.v
+    }
+
+    pub fn gete(&self) -> usize {
+        self.enc.e
+    }
+
+    /// Size in bytes of all of the encoded neighbor lists
+    pub fn neighbs_len(&self) -> usize {
+        self.enc.neighbs_len()
+    }
+
+    /// Check that the offsets are non-decreasing and inside the neighbs
+    /// section, and that every list decodes to exactly its bytes, with
+    /// neighbor ids less than v and e edges in all
+    pub fn validate(&self, parallel: bool) -> Result<()> {
+        let check_run = |u: usize| {
+            let mut out = Vec::new();
+            self.decode(u, &mut out, 0)?;
+            Ok(out.len())
+        };
+        self.enc.validate(parallel, check_run)
+    }
+
+    /*Append vertex x's list to out, in order, decoding the lists it copies
+    from first.  chain is how many references were followed to get here.*/
+    fn decode(&self, x: usize, out: &mut Vec<usize>, chain: usize) -> Result<()> {
+        let v = self.enc.v;
+        let bytes = self.enc.run(x);
+        let bad = || Error::Format(FormatError::BadEncoding { vtx: x });
+        let out_of_range = |n: i128| {
+            Error::Format(FormatError::NeighborOutOfRange {
+                vtx: x,
+                neighbor: n.clamp(0, usize::MAX as i128) as usize,
+                v,
+            })
+        };
+        let mut pos = 0;
+        let mut next = || get_varint(bytes, &mut pos).ok_or_else(bad);
+
+        let d = next()? as usize;
+        let start = out.len();
+        if d > 0 {
+            let r = next()? as usize;
+            if r > 0 {
+                if r > x || chain >= MAX_CHAIN {
+                    return Err(bad());
+                }
+                let mut reflist = Vec::new();
+                self.decode(x - r, &mut reflist, chain + 1)?;
+                let (mut i, mut copying) = (0, true);
+                for _ in 0..next()? {
+                    let end = i + (next()? as usize).min(reflist.len() + 1);
+                    if end > reflist.len() || (copying && out.len() - start + end - i > d) {
+                        return Err(bad());
+                    }
+                    if copying {
+                        out.extend_from_slice(&reflist[i..end]);
+                    }
+                    i = end;
+                    copying = !copying;
+                }
+            }
+
+            let mut prev_end = 0i128;
+            for k in 0..next()? {
+                let left = match k {
+                    0 => x as i128 + unzigzag(next()?) as i128,
+                    _ => prev_end + 2 + next()? as i128,
+                };
+                let len = next()? as i128;
+                if left < 0 || left + len > v as i128 {
+                    return Err(out_of_range(left + len - 1));
+                }
+                if len == 0 || (out.len() - start) as i128 + len > d as i128 {
+                    return Err(bad());
+                }
+                out.extend(left as usize..(left + len) as usize);
+                prev_end = left + len - 1;
+            }
+
+            let mut prev = 0i128;
+            for k in 0..d - (out.len() - start) {
+                prev = match k {
+                    0 => x as i128 + unzigzag(next()?) as i128,
+                    _ => prev + 1 + next()? as i128,
+                };
+                if prev < 0 || prev >= v as i128 {
+                    return Err(out_of_range(prev));
+                }
+                out.push(prev as usize);
+            }
+        }
+
+        if pos != bytes.len() {
+            return Err(bad());
+        }
+        out[start..].sort_unstable();
+        Ok(())
+    }
+
+    /// The number of edges out of vertex v, decoded from the start of its list
+    pub fn out_degree(&self, v: usize) -> usize {
+        let mut pos = 0;
+        get_varint(self.enc.run(v), &mut pos).expect("badly encoded neighbor list") as usize
+    }
+
+    /// Decode the neighbors of vertex v, in increasing order, into buf,
+    /// replacing what it held
+    pub fn neighbors_into(&self, v: usize, buf: &mut Vec<usize>) {
+        buf.clear();
+        if let Err(e) = self.decode(v, buf, 0) {
+            panic!("{}", e);
+        }
+    }
+
+    /// The neighbors of vertex v, in increasing order
+    pub fn neighbors(&self, v: usize) -> Vec<usize> {
+        let mut buf = Vec::new();
+        self.neighbors_into(v, &mut buf);
+        buf
+    }
+
+    /// Apply f to every vertex in parallel, handing it the vertex's decoded
+    /// neighbors
+    pub fn neighbor_scan(&self, f: impl Fn(usize, &[usize]) + std::marker::Sync) {
+        (0..self.enc.v).into_par_iter().for_each_init(Vec::new, |buf, v| {
+            self.neighbors_into(v, buf);
+            f(v, buf);
+        });
+    }
+
+    /// Set each vertex's property to f of the vertex and its neighbors, in parallel
+    pub fn neighbor_scan_prop<P: PropTrait>(
+        &self,
+        f: impl Fn(usize, &[usize]) -> P + std::marker::Sync,
+        prop: &mut [P],
+    ) {
+        prop.par_iter_mut().enumerate().for_each_init(Vec::new, |buf, (v, p)| {
+            self.neighbors_into(v, buf);
+            *p = f(v, buf);
+        });
+    }
+
+    /// Apply f to every edge (v0,v1), in parallel across vertices
+    pub fn read_only_scan(&self, f: impl Fn(usize, usize) + std::marker::Sync) {
+        self.neighbor_scan(|v, nbs| nbs.iter().for_each(|n| f(v, *n)));
+    }
+} /*impl BvGraph*/
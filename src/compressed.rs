@@ -0,0 +1,438 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  Compressed FastCSR files.  Each vertex's neighbors are sorted and stored as
+  a run of LEB128 varints, its degree and then the gaps between neighbors:
+
+  |degree|zigzag(n0 - v)|n1 - n0|n2 - n1|...|
+
+  The first neighbor is coded relative to the vertex itself, since graphs
+  with locality tend to have edges between nearby ids, and can be before or
+  after it.  A gap under 128 takes one byte instead of eight.
+
+  The offsets section holds the byte offset of each vertex's run within the
+  neighbs section, so one vertex's neighbors can be decoded without touching
+  the rest of the file.  Otherwise the file is an ordinary version 2 FastCSR
+  file with FLAG_COMPRESSED set (see format.rs), and no weights or in-edges.
+*/
+
+use byte_slice_cast::*;
+use memmap2::Mmap;
+use rayon::prelude::*;
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::{Error, FormatError, Result};
+use crate::fast_csr::{self, Validation};
+use crate::format::{self, Header};
+use crate::PropTrait;
+
+/*Vertices encoded per parallel task when writing a file*/
+const ENCODE_BLOCK: usize = 1 << 12;
+
+pub(crate) fn put_varint(out: &mut Vec<u8>, mut x: u64) {
+    while x >= 0x80 {
+        out.push((x as u8) | 0x80);
+        x >>= 7;
+    }
+    out.push(x as u8);
+}
+
+/*Decode the varint at bytes[*pos..], moving pos past it.  None if the bytes
+run out or the varint is longer than a u64 can be.*/
+pub(crate) fn get_varint(bytes: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut x = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = *bytes.get(*pos)?;
+        *pos += 1;
+        x |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Some(x);
+        }
+    }
+    None
+}
+
+pub(crate) fn zigzag(d: i64) -> u64 {
+    ((d << 1) ^ (d >> 63)) as u64
+}
+
+pub(crate) fn unzigzag(x: u64) -> i64 {
+    ((x >> 1) as i64) ^ -((x & 1) as i64)
+}
+
+/*Sort vertex v's neighbors and append their encoding to out*/
+fn encode(v: usize, nbs: &mut [usize], out: &mut Vec<u8>) {
+    nbs.sort_unstable();
+    put_varint(out, nbs.len() as u64);
+    let mut prev = v;
+    for (i, n) in nbs.iter().enumerate() {
+        match i {
+            0 => put_varint(out, zigzag(*n as i64 - v as i64)),
+            _ => put_varint(out, (n - prev) as u64),
+        }
+        prev = *n;
+    }
+}
+
+/*Write a file of encoded neighbor lists for a graph of v vertices at path,
+with the given flags on top of FLAG_COMPRESSED.  The vertices are encoded in
+parallel, in blocks of ENCODE_BLOCK: encode(lo, hi, bytes, starts) appends
+the lists of vertices lo..hi to bytes, pushes where each one starts onto
+starts, and returns how many edges it encoded.  The blocks are then streamed
+out in order.*/
+pub(crate) fn write_blocks<Q: AsRef<Path>>(
+    path: Q,
+    v: usize,
+    flags: u64,
+    encode: impl Fn(usize, usize, &mut Vec<u8>, &mut Vec<usize>) -> usize + Sync,
+) -> Result<Header> {
+    let blocks: Vec<(Vec<u8>, Vec<usize>, usize)> = (0..v.div_ceil(ENCODE_BLOCK))
+        .into_par_iter()
+        .map(|b| {
+            let (mut bytes, mut starts) = (Vec::new(), Vec::new());
+            let ne = encode(b * ENCODE_BLOCK, ((b + 1) * ENCODE_BLOCK).min(v), &mut bytes, &mut starts);
+            (bytes, starts, ne)
+        })
+        .collect();
+
+    let mut offsets = Vec::with_capacity(v);
+    let (mut len, mut e) = (0, 0);
+    for (bytes, starts, ne) in &blocks {
+        offsets.extend(starts.iter().map(|s| len + s));
+        len += bytes.len();
+        e += ne;
+    }
+
+    let h = Header::new_compressed(v, e, len, flags);
+    let end = h.file_len().ok_or(FormatError::TooLarge { v, e })?;
+
+    format::write_file(path, |w| {
+        w.write_all(&h.to_bytes())?;
+        w.write_all(&vec![0u8; h.offsets_off - format::HEADER_BYTES])?;
+        w.write_all(offsets.as_byte_slice())?;
+        w.write_all(&vec![0u8; h.neighbs_off - h.offsets_off - offsets.len() * 8])?;
+        for (bytes, _, _) in &blocks {
+            w.write_all(bytes)?;
+        }
+        w.write_all(&vec![0u8; end - h.neighbs_off - len])?;
+        Ok(())
+    })?;
+    Ok(h)
+}
+
+/*Write a compressed FastCSR file at path for a graph of v vertices, where
+list(u, buf) appends vertex u's neighbors to buf, marked directed if
+directed is true*/
+pub(crate) fn write<Q: AsRef<Path>>(
+    path: Q,
+    v: usize,
+    directed: bool,
+    list: impl Fn(usize, &mut Vec<usize>) + Sync,
+) -> Result<Header> {
+    let mut flags = format::FLAG_SORTED;
+    if directed {
+        flags |= format::FLAG_DIRECTED;
+    }
+    write_blocks(path, v, flags, |lo, hi, bytes, starts| {
+        let mut buf = Vec::new();
+        let mut ne = 0;
+        for u in lo..hi {
+            buf.clear();
+            list(u, &mut buf);
+            starts.push(bytes.len());
+            ne += buf.len();
+            encode(u, &mut buf, bytes);
+        }
+        ne
+    })
+}
+
+/*A mapped file of encoded neighbor lists, shared by CompressedFastCSR and
+BvGraph: the usual header, a byte offset per vertex, and the lists*/
+pub(crate) struct Encoded {
+    pub(crate) v: usize,
+    pub(crate) e: usize,
+    obase: usize,
+    oend: usize,
+    nbase: usize,
+    nend: usize,
+    raw: Box<Mmap>,
+}
+
+impl Encoded {
+    /*Map the file at path, failing unless it is compressed, with reference
+    compression (FLAG_REFERENCES) if references is set and without otherwise*/
+    pub(crate) fn open(path: &Path, references: bool) -> Result<Encoded> {
+        let raw = fast_csr::map(path)?;
+        let h = Header::parse(&raw)?;
+        if !h.is_compressed() {
+            return Err(Error::Format(FormatError::Compressed(false)));
+        }
+        if h.has_references() != references {
+            return Err(Error::Format(FormatError::References(h.has_references())));
+        }
+        let w = std::mem::size_of::<usize>();
+        if h.offset_width() != w {
+            return Err(Error::Format(FormatError::IndexWidth {
+                id: h.id_width(),
+                offset: h.offset_width(),
+            }));
+        }
+
+        let (v, e) = (h.v, h.e);
+        let need = h.file_len().ok_or(FormatError::TooLarge { v, e })?;
+        if raw.len() < need {
+            return Err(Error::Format(FormatError::Truncated {
+                len: raw.len(),
+                need,
+            }));
+        }
+
+        Ok(Encoded {
+            v,
+            e,
+            obase: h.offsets_off,
+            oend: h.offsets_off + v * w,
+            nbase: h.neighbs_off,
+            nend: h.neighbs_off + h.neighbs_len,
+            raw,
+        })
+    }
+
+    pub(crate) fn neighbs_len(&self) -> usize {
+        self.nend - self.nbase
+    }
+
+    fn offsets(&self) -> &[usize] {
+        self.raw[self.obase..self.oend].as_slice_of::<usize>().unwrap()
+    }
+
+    /*The encoded bytes of vertex v's neighbor list*/
+    pub(crate) fn run(&self, v: usize) -> &[u8] {
+        let offsets = self.offsets();
+        let en = match v {
+            v if v == self.v - 1 => self.neighbs_len(),
+            _ => offsets[v + 1],
+        };
+        &self.raw[self.nbase + offsets[v]..self.nbase + en]
+    }
+
+    /*Check that the offsets are non-decreasing and inside the lists, then
+    check each list with check_run, which returns its degree, and that the
+    degrees add up to e*/
+    pub(crate) fn validate(
+        &self,
+        parallel: bool,
+        check_run: impl Fn(usize) -> Result<usize> + Sync,
+    ) -> Result<()> {
+        let offsets = self.offsets();
+        let bad_offset = |i: &usize| match *i {
+            0 => offsets[0] != 0,
+            i => offsets[i] < offsets[i - 1],
+        };
+        let bad = match parallel {
+            true => (0..self.v).into_par_iter().find_first(bad_offset),
+            false => (0..self.v).find(bad_offset),
+        };
+        match bad {
+            Some(0) => return Err(Error::Format(FormatError::FirstOffsetNonZero(offsets[0]))),
+            Some(vtx) => return Err(Error::Format(FormatError::OffsetsNotMonotonic { vtx })),
+            None => (),
+        }
+        if self.v > 0 && offsets[self.v - 1] > self.neighbs_len() {
+            return Err(Error::Format(FormatError::OffsetOutOfRange {
+                vtx: self.v - 1,
+                offset: offsets[self.v - 1],
+                e: self.neighbs_len(),
+            }));
+        }
+
+        let count = match parallel {
+            true => (0..self.v)
+                .into_par_iter()
+                .map(&check_run)
+                .collect::<Result<Vec<usize>>>()?
+                .into_iter()
+                .sum(),
+            false => (0..self.v).map(&check_run).sum::<Result<usize>>()?,
+        };
+        if count != self.e {
+            return Err(Error::Format(FormatError::EdgeCount { count, e: self.e }));
+        }
+        Ok(())
+    }
+}
+
+/// The neighbors of one vertex of a CompressedFastCSR, decoded one at a time
+/// as the iterator is advanced, in increasing order
+#[derive(Debug, Clone)]
+pub struct Neighbors<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    left: usize,
+    prev: usize,
+    first: bool,
+}
+
+impl<'a> Neighbors<'a> {
+    fn new(v: usize, bytes: &'a [u8]) -> Neighbors<'a> {
+        let mut pos = 0;
+        let left = get_varint(bytes, &mut pos).expect("badly encoded neighbor list") as usize;
+        Neighbors {
+            bytes,
+            pos,
+            left,
+            prev: v,
+            first: true,
+        }
+    }
+}
+
+impl<'a> Iterator for Neighbors<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.left == 0 {
+            return None;
+        }
+        let x = get_varint(self.bytes, &mut self.pos).expect("badly encoded neighbor list");
+        self.prev = match self.first {
+            true => (self.prev as i64).wrapping_add(unzigzag(x)) as usize,
+            false => self.prev.wrapping_add(x as usize),
+        };
+        self.first = false;
+        self.left -= 1;
+        Some(self.prev)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.left, Some(self.left))
+    }
+}
+
+impl<'a> ExactSizeIterator for Neighbors<'a> {}
+
+/// A FastCSR file whose neighbor lists are gap- and varint-encoded (see
+/// CSR::write_compressed_fastcsr).  Lists are decoded on the fly by the
+/// Neighbors iterators that neighbors and the scans hand out, so the file
+/// is never decompressed as a whole.
+pub struct CompressedFastCSR {
+    enc: Encoded,
+}
+
+impl CompressedFastCSR {
+    /// Load a compressed FastCSR file, decoding every neighbor list (in
+    /// parallel) to check it before returning
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<CompressedFastCSR> {
+        CompressedFastCSR::open_with(path, Validation::Parallel)
+    }
+
+    /// Load a compressed FastCSR file, checking as much of it as the
+    /// validation level asks for.  Fails with Compressed(false) if the file
+    /// is an ordinary FastCSR file.
+    pub fn open_with<P: AsRef<Path>>(path: P, check: Validation) -> Result<CompressedFastCSR> {
+        let g = CompressedFastCSR {
+            enc: Encoded::open(path.as_ref(), false)?,
+        };
+
+        match check {
+            Validation::Header => (),
+            Validation::Sequential => g.validate(false)?,
+            Validation::Parallel => g.validate(true)?,
+        }
+
+        Ok(g)
+    }
+
+    pub fn getv(&self) -> usize {
+        self.enc.v
+    }
+
+    pub fn gete(&self) -> usize {
+        self.enc.e
+    }
+
+    /// Size in bytes of all of the encoded neighbor lists
+    pub fn neighbs_len(&self) -> usize {
+        self.enc.neighbs_len()
+    }
+
+    /// Check that the offsets are non-decreasing and inside the neighbs
+    /// section, and that every list decodes to exactly its bytes, with
+    /// neighbor ids less than v and e edges in all.  Reports the first
+    /// problem found.
+    pub fn validate(&self, parallel: bool) -> Result<()> {
+        self.enc.validate(parallel, |u| self.check_run(u))
+    }
+
+    /*Decode vertex u's list without trusting it, returning its degree*/
+    fn check_run(&self, u: usize) -> Result<usize> {
+        let bytes = self.enc.run(u);
+        let bad = || Error::Format(FormatError::BadEncoding { vtx: u });
+        let mut pos = 0;
+        let deg = get_varint(bytes, &mut pos).ok_or_else(bad)?;
+        let mut prev = u as i128;
+        for i in 0..deg {
+            let x = get_varint(bytes, &mut pos).ok_or_else(bad)?;
+            prev += match i {
+                0 => unzigzag(x) as i128,
+                _ => x as i128,
+            };
+            if prev < 0 || prev >= self.enc.v as i128 {
+                return Err(Error::Format(FormatError::NeighborOutOfRange {
+                    vtx: u,
+                    neighbor: prev.max(0) as usize,
+                    v: self.enc.v,
+                }));
+            }
+        }
+        match pos == bytes.len() {
+            true => Ok(deg as usize),
+            false => Err(bad()),
+        }
+    }
+
+    /// The number of edges out of vertex v, decoded from the start of its list
+    pub fn out_degree(&self, v: usize) -> usize {
+        self.neighbors(v).len()
+    }
+
+    /// The neighbors of vertex v, in increasing order
+    pub fn neighbors(&self, v: usize) -> Neighbors<'_> {
+        Neighbors::new(v, self.enc.run(v))
+    }
+
+    /// Apply f to every vertex in parallel, handing it an iterator over the
+    /// vertex's neighbors
+    pub fn neighbor_scan(&self, f: impl Fn(usize, Neighbors<'_>) + std::marker::Sync) {
+        (0..self.enc.v).into_par_iter().for_each(|v| f(v, self.neighbors(v)));
+    }
+
+    /// Set each vertex's property to f of the vertex and its neighbors, in parallel
+    pub fn neighbor_scan_prop<P: PropTrait>(
+        &self,
+        f: impl Fn(usize, Neighbors<'_>) -> P + std::marker::Sync,
+        prop: &mut [P],
+    ) {
+        prop.par_iter_mut().enumerate().for_each(|(v, p)| {
+            *p = f(v, self.neighbors(v));
+        });
+    }
+
+    /// Apply f to every edge (v0,v1), in parallel across vertices
+    pub fn read_only_scan(&self, f: impl Fn(usize, usize) + std::marker::Sync) {
+        self.neighbor_scan(|v, nbs| nbs.for_each(|n| f(v, n)));
+    }
+} /*impl CompressedFastCSR*/
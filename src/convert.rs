@@ -0,0 +1,117 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use byte_slice_cast::*;
+use memmap2::MmapMut;
+use std::path::Path;
+
+use crate::csr::{self, BuildOptions};
+use crate::error::{FormatError, Result};
+use crate::format::{self, AtomicFile, Header};
+use crate::place;
+
+/// Convert the binary edge list at el (pairs of 8-byte vertex ids) into a
+/// FastCSR file at out without building a CSR in memory.  The output file is
+/// sized from the vertices' degrees and the offsets and neighbors are placed
+/// straight into it through a writable mapping.  With the default options
+/// the only memory needed beyond the two mapped files is a few words per
+/// vertex.
+///
+/// The graph has numv vertices, failing with VertexOutOfRange if an edge
+/// names a vertex >= numv, or if numv is None, one more than the largest
+/// id in the edge list.  The build options work as they do for a CSR, and
+/// some cost memory beyond the mapped files: propagation_blocking bins every
+/// edge in memory, three words per edge, before placing it; deterministic
+/// keeps per-thread degree counts, up to one word per edge; and dedup copies
+/// each vertex's neighbors while it removes repeats, up to one word per edge
+/// of the vertices being deduplicated at once.
+/// The file is written under a temporary name and renamed to out once
+/// it is complete.  Returns the header written to the file.
+pub fn el_to_fastcsr<P: AsRef<Path>, Q: AsRef<Path>>(
+    el: P,
+    out: Q,
+    numv: Option<usize>,
+    opts: &BuildOptions,
+) -> Result<Header> {
+    let mmap = csr::map_el(el, 2)?;
+    let el = mmap[..]
+             .as_slice_of::<usize>()
+             .map_err(|_| FormatError::Misaligned { section: "edge list", off: 0 })?;
+    let v = match numv {
+        Some(v) => {
+            csr::check_el_range(el, 2, v)?;
+            v
+        }
+        None => csr::el_numv(el, 2, None)?,
+    };
+
+    let (n, edge) = place::expand(el.len() / 2, opts, |i| (el[2 * i], el[2 * i + 1]));
+    let nowt = |_: usize| 0u64;
+    let (degs, plan) = place::count(v, n, &edge, &nowt, opts);
+    let e: usize = degs.iter().sum();
+
+    let mut flags = 0;
+    if !opts.symmetrize {
+        flags |= format::FLAG_DIRECTED;
+    }
+    if opts.sort || opts.dedup {
+        flags |= format::FLAG_SORTED;
+    }
+    let mut h = Header::new(v, e, flags);
+    let len = h.file_len().ok_or(FormatError::TooLarge { v, e })?;
+
+    let out = AtomicFile::new(out);
+    let file = out.create()?;
+    file.set_len(len as u64)?;
+    let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+
+    /*The mapping is page aligned and every section starts on an 8-byte
+    boundary, so the sections can be used as word slices in place*/
+    let (_, rest) = mmap.split_at_mut(h.offsets_off);
+    let (obytes, rest) = rest.split_at_mut(h.neighbs_off - h.offsets_off);
+    let offsets = obytes.as_mut_slice_of::<usize>().unwrap();
+    let nbs = rest[..e * 8].as_mut_slice_of::<usize>().unwrap();
+
+    place::offsets_into(&degs, offsets);
+    place::place(plan, v, n, &edge, &nowt, offsets, nbs, &mut []);
+
+    if opts.sort || opts.dedup {
+        let mut kept = degs;
+        place::sort_segments::<usize, u64>(&mut kept, nbs, &mut [], opts.dedup);
+
+        /*Slide each vertex's kept neighbors down over the dropped duplicates*/
+        let ne: usize = kept.iter().sum();
+        if ne != e {
+            let mut dst = 0;
+            for (off, k) in offsets.iter().zip(kept.iter()) {
+                nbs.copy_within(*off..*off + *k, dst);
+                dst += *k;
+            }
+            place::offsets_into(&kept, offsets);
+            h = Header::new(v, ne, flags);
+        }
+    }
+
+    mmap[..format::HEADER_BYTES].copy_from_slice(&h.to_bytes());
+    mmap.flush()?;
+    drop(mmap);
+
+    /*Dedup may have left the file longer than the graph it holds*/
+    let need = h.file_len().ok_or(FormatError::TooLarge { v, e })?;
+    if need != len {
+        file.set_len(need as u64)?;
+    }
+    out.commit(file)?;
+
+    Ok(h)
+}
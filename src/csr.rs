@@ -16,52 +16,188 @@ extern crate csv;
 extern crate rand;
 
 use bit_vec::BitVec;
-use memmap2::{MmapMut,Mmap};
+use memmap2::Mmap;
 use rand::Rng;
 use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
+use std::io::{self, Write};
 use std::fs::OpenOptions;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::path::{Path, PathBuf};
 use byte_slice_cast::*;
 
-#[derive(Debug)]
-pub struct CSR {
-    v: usize,
-    e: usize,
-    vtxprop: Vec<f64>,
-    offsets: Vec<usize>,
-    neighbs: Vec<usize>,
-}
+use crate::bfs::{self, BfsTree, Direction, DirectionOptions};
+use crate::compressed;
+use crate::dfs::{self, DfsVisitor};
+use crate::dimacs;
+use crate::error::{Error, FormatError, Result};
+use crate::format::{self, AtomicFile, Header};
+use crate::galois;
+use crate::index::{self, Idx};
+use crate::ligra;
+use crate::metis;
+use crate::mtx;
+use crate::text_el::{self, TextFormat};
+use crate::place;
+use crate::weight::Weight;
+use crate::PropTrait;
 
-impl CSR {
-    pub fn get_vtxprop(&self) -> &[f64] {
-        &self.vtxprop
+/*Turn a csv reader error into an Error, keeping the line it happened on*/
+fn csv_error(e: csv::Error) -> Error {
+    let line = e.position().map_or(0, |pos| pos.line());
+    let msg = e.to_string();
+    match e.into_kind() {
+        csv::ErrorKind::Io(e) => Error::Io(e),
+        _ => Error::parse(line, msg),
     }
+}
 
-    pub fn get_mut_vtxprop(&mut self) -> &mut [f64] {
-        &mut self.vtxprop
+/*Read a headerless comma separated file, handing f each record and its line number*/
+fn read_csv(path: &str, mut f: impl FnMut(u64, &csv::StringRecord) -> Result<()>) -> Result<()> {
+    let file = File::open(path)?;
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(file);
+    for result in rdr.records() {
+        let p = result.map_err(csv_error)?;
+        f(p.position().map_or(0, |pos| pos.line()), &p)?;
     }
+    Ok(())
+}
 
-    pub fn get_v(&self) -> usize {
-        self.v
-    }
+/*Parse field i of a csv record, naming what was expected if it is missing or bad*/
+fn csv_field<T>(
+    p: &csv::StringRecord,
+    line: u64,
+    i: usize,
+    what: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<T> {
+    let s = p
+        .get(i)
+        .ok_or_else(|| Error::parse(line, format!("missing {}", what)))?;
+    parse(s).ok_or_else(|| Error::parse(line, format!("bad {} {:?}", what, s)))
+}
 
-    pub fn get_e(&self) -> usize {
-        self.e
+/*Map a binary edge list of words_per_edge 8-byte words per edge*/
+pub(crate) fn map_el<Q: AsRef<Path>>(f: Q, words_per_edge: usize) -> Result<Mmap> {
+    let file = OpenOptions::new()
+               .read(true)
+               .open(f.as_ref())?;
+
+    let mmap = unsafe { Mmap::map(&file)? };
+    if mmap.len() % (8 * words_per_edge) != 0 {
+        return Err(FormatError::EdgeListLength(mmap.len()).into());
     }
+    Ok(mmap)
+}
 
-    pub fn get_offsets(&self) -> &Vec<usize> {
-        &self.offsets
+/*Find the first vertex id in a binary edge list that is >= v.  Only the
+first two words of each edge are vertex ids.*/
+pub(crate) fn check_el_range(el: &[usize], words_per_edge: usize, v: usize) -> Result<()> {
+    let bad = el
+        .par_chunks(words_per_edge)
+        .find_first(|e| e[0] >= v || e[1] >= v);
+    match bad {
+        Some(e) => Err(Error::VertexOutOfRange {
+            vtx: if e[0] >= v { e[0] } else { e[1] },
+            v,
+        }),
+        None => Ok(()),
     }
+}
 
-    pub fn get_neighbs(&self) -> &[usize] {
-        &self.neighbs
+/*The number of vertices a binary edge list needs, one more than the largest
+vertex id in it, found with a parallel max reduction.  If a bound is given,
+fails if any id is not less than it.*/
+pub(crate) fn el_numv(el: &[usize], words_per_edge: usize, bound: Option<usize>) -> Result<usize> {
+    let maxv = el
+        .par_chunks(words_per_edge)
+        .map(|e| e[0].max(e[1]))
+        .max();
+    match (maxv, bound) {
+        (Some(vtx), Some(v)) if vtx >= v => Err(Error::VertexOutOfRange { vtx, v }),
+        (Some(vtx), _) => vtx.checked_add(1).ok_or_else(|| {
+            Error::Format(FormatError::TooLarge {
+                v: vtx,
+                e: el.len() / words_per_edge,
+            })
+        }),
+        (None, _) => Ok(0),
     }
+}
+
+/// An edge list, (v0,v1)
+pub type EdgeList = Vec<(usize, usize)>;
+
+/// An edge list whose edges carry weights, (v0,v1,w)
+pub type WeightedEdgeList<W> = Vec<(usize, usize, W)>;
+
+/// Options controlling how a CSR is built from an edge list.  The default
+/// builds the graph exactly as the edge list describes it, with each
+/// vertex's neighbors in no particular order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuildOptions {
+    /// Add the edge (v1,v0), with the same weight, for every edge (v0,v1)
+    pub symmetrize: bool,
+    /// Drop every edge (v,v)
+    pub remove_self_loops: bool,
+    /// Keep only one of each repeated edge (v0,v1), the one with the
+    /// smallest weight in Weight::total_cmp order, where a NaN comes after
+    /// every number unless its sign bit is set.  Implies sort.
+    pub dedup: bool,
+    /// List each vertex's neighbors in increasing order
+    pub sort: bool,
+    /// Place each vertex's neighbors in the order their edges appear in the
+    /// edge list (with the reverse edges added by symmetrize after all of
+    /// the original ones), so that every build of the same edge list gives
+    /// the same graph.  This costs a count per vertex per thread.
+    pub deterministic: bool,
+    /// Place edges with propagation blocking instead of atomic counters:
+    /// each thread first bins its edges by block of sources, then each block
+    /// is placed by one thread whose cursors stay in cache.  Costs two to
+    /// three extra words per edge while building.  Neighbors come out in
+    /// edge list order, as with deterministic.
+    pub propagation_blocking: bool,
+}
+
+/// A CSR graph.  Each vertex has a property of type P, stored in vtxprop,
+/// and edges can optionally carry a weight of type W, stored in a weights
+/// array parallel to the neighbs array.  The graph can also keep its own
+/// transpose (see with_in_edges) to answer in_neighbors queries.
+///
+/// Neighbor ids are stored as I and offsets into neighbs as O, usize by
+/// default.  A graph with fewer than 2^32 vertices (or edges) can store
+/// them as u32 to halve their memory; see CompactCSR and with_index.
+#[derive(Debug)]
+pub struct CSR<P = f64, W = f64, I = usize, O = usize> {
+    v: usize,
+    e: usize,
+    vtxprop: Vec<P>,
+    offsets: Vec<O>,
+    neighbs: Vec<I>,
+    weights: Option<Vec<W>>,
+    in_edges: Option<Box<CSR<(), W, I, O>>>,
+    /*Known to have every vertex's neighbors in increasing order*/
+    sorted: bool,
+    /*Built with symmetrize, so every edge (v0,v1) has a matching (v1,v0)*/
+    symmetric: bool,
+}
 
+/// A CSR with 4-byte neighbor ids and offsets, for graphs with fewer than
+/// 2^32 vertices and edges
+pub type CompactCSR<P = f64, W = f64> = CSR<P, W, u32, u32>;
+
+/*The graph's counts as a TooLarge error, for when they do not fit an index type*/
+fn too_large(v: usize, e: usize) -> Error {
+    Error::Format(FormatError::TooLarge { v, e })
+}
+
+impl CSR {
     /// Build a random edge list
     /// This method returns a tuple of the number of vertices seen and the edge list
-    /// el.len() is the number of edges.  
+    /// el.len() is the number of edges.
     pub fn random_el(numv: usize, maxe: usize) -> Vec<(usize, usize)> {
         let mut rng = rand::thread_rng();
         let mut el: Vec<(usize, usize)> = Vec::new();
@@ -69,7 +205,7 @@ impl CSR {
             /*edges per vertex*/
             let num_e: usize = rng.gen_range(0, maxe) as usize;
             for _ in 0..num_e {
-                let edge = (i as usize, rng.gen_range(0, numv) as usize);
+                let edge = (i, rng.gen_range(0, numv) as usize);
 
                 el.push(edge);
             }
@@ -91,214 +227,587 @@ impl CSR {
     /// ...
     ///
     /// This method returns a tuple of the number of vertices seen and the edge list
-    /// el.len() is the number of edges.  
-    pub fn el_from_file(path: &str) -> (usize, Vec<(usize, usize)>) {
+    /// el.len() is the number of edges.  It fails if the file cannot be read
+    /// or a line is not a pair of vertex ids.
+    pub fn el_from_file(path: &str) -> Result<(usize, Vec<(usize, usize)>)> {
         let mut el: Vec<(usize, usize)> = Vec::new();
         let mut maxv: usize = 0;
 
-        let f = File::open(path);
-
-        match f {
-            Ok(file) => {
-                let mut rdr = csv::ReaderBuilder::new()
-                    .has_headers(false)
-                    .from_reader(file);
-                for result in rdr.records() {
-                    match result {
-                        Ok(p) => {
-                            let v0 = p.get(0).unwrap().parse::<usize>().unwrap();
-                            let v1 = p.get(1).unwrap().parse::<usize>().unwrap();
-                            if v0 > maxv {
-                                maxv = v0
-                            }
-                            if v1 > maxv {
-                                maxv = v1
-                            }
-                            el.push((v0, v1));
-                        }
-                        _ => {
-                            eprintln!("Failed to parse file");
-                        }
-                    }
-                }
+        read_csv(path, |line, p| {
+            let vtx = |i| csv_field(p, line, i, "vertex id", |s| s.trim().parse::<usize>().ok());
+            let v0 = vtx(0)?;
+            let v1 = vtx(1)?;
+            if v0 > maxv {
+                maxv = v0
             }
-            _ => {
-                eprintln!("Failed to open file {}", path);
+            if v1 > maxv {
+                maxv = v1
             }
-        }
+            el.push((v0, v1));
+            Ok(())
+        })?;
 
-        (maxv + 1, el)
+        Ok((maxv + 1, el))
     }
 
-    pub fn new_from_el_mmap(v: usize, f: String) -> CSR {
+    /// Build a CSR with v vertices from a file holding a binary edge list,
+    /// a sequence of pairs of 8-byte vertex ids.  Fails if the file cannot
+    /// be mapped, is not a whole number of pairs, or names a vertex >= v.
+    pub fn new_from_el_mmap(v: usize, f: String) -> Result<CSR> {
+        CSR::new_from_el_mmap_with(v, f, &BuildOptions::default())
+    }
 
-        let path = PathBuf::from(f);
-        let file = OpenOptions::new()
-                   .read(true)
-                   .open(&path)
-                   .unwrap();
+    /// Build a CSR from a file holding a binary edge list without knowing
+    /// the number of vertices up front: it is one more than the largest id
+    /// in the file.  If bound is given, fails with VertexOutOfRange if any
+    /// id is not less than it.
+    pub fn new_from_el_mmap_auto(f: String, bound: Option<usize>) -> Result<CSR> {
+        CSR::new_from_el_mmap_auto_with(f, bound, &BuildOptions::default())
+    }
 
-        let mmap = unsafe { Mmap::map(&file).unwrap() };
+    /// Like new_from_el_mmap_auto, but built as the options describe
+    pub fn new_from_el_mmap_auto_with(
+        f: String,
+        bound: Option<usize>,
+        opts: &BuildOptions,
+    ) -> Result<CSR> {
+        let mmap = map_el(f, 2)?;
         let el = mmap[..]
                  .as_slice_of::<usize>()
-                 .unwrap();
+                 .map_err(|_| FormatError::Misaligned { section: "edge list", off: 0 })?;
+        let v = el_numv(el, 2, bound)?;
 
-        let mut ncnt  = Vec::with_capacity(v);
-        for _ in 0..v {
-            ncnt.push(AtomicUsize::new(0));
-        }
+        CSR::build(
+            v,
+            el.len() / 2,
+            |i| (el[2 * i], el[2 * i + 1]),
+            None,
+            vec![0f64; v],
+            opts,
+        )
+    }
 
-        /*Count up the number of neighbors that each vertex has */
-        el.chunks(2).par_bridge().for_each(|e| {
-            ncnt[ e[0] ].fetch_add(1, Ordering::SeqCst);
-        });
+    /// Like new_from_el_mmap, but built as the options describe
+    pub fn new_from_el_mmap_with(v: usize, f: String, opts: &BuildOptions) -> Result<CSR> {
+        let mmap = map_el(f, 2)?;
+        let el = mmap[..]
+                 .as_slice_of::<usize>()
+                 .map_err(|_| FormatError::Misaligned { section: "edge list", off: 0 })?;
+        check_el_range(el, 2, v)?;
+
+        CSR::build(
+            v,
+            el.len() / 2,
+            |i| (el[2 * i], el[2 * i + 1]),
+            None,
+            vec![0f64; v],
+            opts,
+        )
+    }
+
+    /// Take an edge list in and produce a CSR out
+    /// (u,v)
+    pub fn new(numv: usize, el: Vec<(usize, usize)>) -> CSR {
+        CSR::new_with(numv, el, &BuildOptions::default())
+    }
+
+    /// Like new, but built as the options describe, e.g., to get an
+    /// undirected graph with sorted neighbors and no repeated edges:
+    /// CSR::new_with(numv, el, &BuildOptions { symmetrize: true, dedup: true, ..Default::default() })
+    pub fn new_with(numv: usize, el: Vec<(usize, usize)>, opts: &BuildOptions) -> CSR {
+        CSR::build(numv, el.len(), |i| el[i], None, vec![0f64; numv], opts)
+            .expect("usize ids and offsets fit any graph")
+    }
+
+    /// Build a CSR from a Matrix Market coordinate file (see mtx.rs),
+    /// ignoring any values, as the options describe.  The graph has as many
+    /// vertices as the matrix has rows or columns, whichever is more, and a
+    /// symmetric file's entries stand for edges in both directions.
+    pub fn new_from_mtx(path: &str, opts: &BuildOptions) -> Result<CSR> {
+        let (h, el) = mtx::read_el(path)?;
+        Ok(CSR::new_with(h.numv(), el, opts))
+    }
+
+    /// Build a CSR from a METIS .graph file (see metis.rs), ignoring any
+    /// weights, as the options describe.  Each undirected edge becomes two
+    /// directed edges, one listed on each of its ends' lines.
+    pub fn new_from_metis(path: &str, opts: &BuildOptions) -> Result<CSR> {
+        let (h, el) = metis::read_el(path)?;
+        Ok(CSR::new_with(h.v, el, opts))
+    }
+
+    /// Build a CSR from a DIMACS shortest path .gr file (see dimacs.rs),
+    /// ignoring the arcs' weights, as the options describe
+    pub fn new_from_dimacs(path: &str, opts: &BuildOptions) -> Result<CSR> {
+        let (v, el) = dimacs::read_el(path)?;
+        Ok(CSR::new_with(v, el, opts))
+    }
+
+    /// Build a CSR from a Ligra AdjacencyGraph file (see ligra.rs),
+    /// ignoring any weights, as the options describe
+    pub fn new_from_ligra(path: &str, opts: &BuildOptions) -> Result<CSR> {
+        let (v, el) = ligra::read_el(path)?;
+        Ok(CSR::new_with(v, el, opts))
+    }
+
+    /// Build a CSR from a text edge list laid out as fmt says (see
+    /// text_el.rs), e.g., a SNAP file with TextFormat::snap().  The graph
+    /// has one more vertex than the largest id in the file.
+    pub fn new_from_text<Q: AsRef<Path>>(path: Q, fmt: &TextFormat, opts: &BuildOptions) -> Result<CSR> {
+        let (v, el) = text_el::read_el(path, fmt)?;
+        Ok(CSR::new_with(v, el, opts))
+    }
+} /*impl CSR*/
+
+impl<W: Weight> CSR<f64, W> {
+    /// Build a weighted edge list from a file containing text describing one.
+    /// The format is the same as el_from_file's, with a weight as a third field:
+    /// v0,v1,w
+    /// v0,v2,w
+    /// ...
+    ///
+    /// This method returns a tuple of the number of vertices seen and the edge list
+    pub fn wel_from_file(path: &str) -> Result<(usize, WeightedEdgeList<W>)> {
+        let mut el: WeightedEdgeList<W> = Vec::new();
+        let mut maxv: usize = 0;
+
+        read_csv(path, |line, p| {
+            let vtx = |i| csv_field(p, line, i, "vertex id", |s| s.trim().parse::<usize>().ok());
+            let v0 = vtx(0)?;
+            let v1 = vtx(1)?;
+            let w = csv_field(p, line, 2, "weight", W::parse_weight)?;
+            maxv = maxv.max(v0).max(v1);
+            el.push((v0, v1, w));
+            Ok(())
+        })?;
+
+        Ok((maxv + 1, el))
+    }
+
+    /// Build a weighted CSR with v vertices from a file holding a binary
+    /// edge list of triples of 8-byte words (v0, v1, w).  The weight word is
+    /// decoded with Weight::from_word, so a f64 weight is stored as its bits.
+    pub fn new_from_wel_mmap(v: usize, f: String) -> Result<CSR<f64, W>> {
+        CSR::new_from_wel_mmap_with(v, f, &BuildOptions::default())
+    }
+
+    /// Like new_from_wel_mmap, but built as the options describe
+    pub fn new_from_wel_mmap_with(v: usize, f: String, opts: &BuildOptions) -> Result<CSR<f64, W>> {
+        let mmap = map_el(f, 3)?;
+        let el = mmap[..]
+                 .as_slice_of::<usize>()
+                 .map_err(|_| FormatError::Misaligned { section: "edge list", off: 0 })?;
+        check_el_range(el, 3, v)?;
+
+        let weight = |i: usize| W::from_word(el[3 * i + 2] as u64);
+        CSR::build(
+            v,
+            el.len() / 3,
+            |i| (el[3 * i], el[3 * i + 1]),
+            Some(&weight),
+            vec![0f64; v],
+            opts,
+        )
+    }
+
+    /// Take a weighted edge list in and produce a weighted CSR out
+    /// (u,v,w)
+    pub fn new_weighted(numv: usize, el: WeightedEdgeList<W>) -> CSR<f64, W> {
+        CSR::new_weighted_with(numv, el, &BuildOptions::default())
+    }
+
+    /// Like new_weighted, but built as the options describe
+    pub fn new_weighted_with(numv: usize, el: WeightedEdgeList<W>, opts: &BuildOptions) -> CSR<f64, W> {
+        let weight = |i: usize| el[i].2;
+        CSR::build(
+            numv,
+            el.len(),
+            |i| (el[i].0, el[i].1),
+            Some(&weight),
+            vec![0f64; numv],
+            opts,
+        )
+        .expect("usize ids and offsets fit any graph")
+    }
+
+    /// Like new_from_mtx, but with each entry's value as its edge's weight.
+    /// Fails on a pattern file, which has no values.
+    pub fn new_weighted_from_mtx(path: &str, opts: &BuildOptions) -> Result<CSR<f64, W>> {
+        let (h, el) = mtx::read_wel::<W>(path)?;
+        Ok(CSR::new_weighted_with(h.numv(), el, opts))
+    }
+
+    /// Like new_from_metis, but with the file's edge weights.  Fails if it
+    /// has none.
+    pub fn new_weighted_from_metis(path: &str, opts: &BuildOptions) -> Result<CSR<f64, W>> {
+        let (h, el) = metis::read_wel::<W>(path)?;
+        Ok(CSR::new_weighted_with(h.v, el, opts))
+    }
+
+    /// Like new_from_dimacs, but with the arcs' weights
+    pub fn new_weighted_from_dimacs(path: &str, opts: &BuildOptions) -> Result<CSR<f64, W>> {
+        let (v, el) = dimacs::read_wel::<W>(path)?;
+        Ok(CSR::new_weighted_with(v, el, opts))
+    }
 
-        let mut work_offsets = Vec::with_capacity(v);
-        work_offsets.push(AtomicUsize::new(0));
+    /// Like new_from_ligra, but with the weights of a
+    /// WeightedAdjacencyGraph file.  Fails if the file has none.
+    pub fn new_weighted_from_ligra(path: &str, opts: &BuildOptions) -> Result<CSR<f64, W>> {
+        let (v, el) = ligra::read_wel::<W>(path)?;
+        Ok(CSR::new_weighted_with(v, el, opts))
+    }
+
+    /// Like new_from_text, but with the weight in fmt's weight column
+    pub fn new_weighted_from_text<Q: AsRef<Path>>(
+        path: Q,
+        fmt: &TextFormat,
+        opts: &BuildOptions,
+    ) -> Result<CSR<f64, W>> {
+        let (v, el) = text_el::read_wel::<W, Q>(path, fmt)?;
+        Ok(CSR::new_weighted_with(v, el, opts))
+    }
+}
+
+impl<I: Idx, O: Idx> CSR<f64, f64, I, O> {
+    /// Like new_with, but storing ids as I and offsets as O, e.g.,
+    /// CompactCSR::new_compact(numv, el, &opts) for 4-byte ids and offsets.
+    /// Fails with TooLarge if the graph has too many vertices or edges for them.
+    pub fn new_compact(numv: usize, el: Vec<(usize, usize)>, opts: &BuildOptions) -> Result<CSR<f64, f64, I, O>> {
+        CSR::build(numv, el.len(), |i| el[i], None, vec![0f64; numv], opts)
+    }
+
+    /// Like new_from_el_mmap_with, but storing ids as I and offsets as O.
+    /// The edge list still holds 8-byte ids; only the graph built from it is
+    /// narrower.
+    pub fn new_compact_from_el_mmap(v: usize, f: String, opts: &BuildOptions) -> Result<CSR<f64, f64, I, O>> {
+        let mmap = map_el(f, 2)?;
+        let el = mmap[..]
+                 .as_slice_of::<usize>()
+                 .map_err(|_| FormatError::Misaligned { section: "edge list", off: 0 })?;
+        check_el_range(el, 2, v)?;
+
+        CSR::build(
+            v,
+            el.len() / 2,
+            |i| (el[2 * i], el[2 * i + 1]),
+            None,
+            vec![0f64; v],
+            opts,
+        )
+    }
+}
+
+impl<P: PropTrait, W: Weight, I: Idx, O: Idx> CSR<P, W, I, O> {
+    /*Every constructor ends up here.  edge(i) is the i'th of the ne edges
+    and weight(i), for a weighted graph, is its weight.  Fails only if the
+    graph is too large for I and O.*/
+    fn build(
+        numv: usize,
+        ne: usize,
+        edge: impl Fn(usize) -> (usize, usize) + Sync,
+        weight: Option<&(dyn Fn(usize) -> W + Sync)>,
+        vtxprop: Vec<P>,
+        opts: &BuildOptions,
+    ) -> Result<CSR<P, W, I, O>> {
+        let (n, edge) = place::expand(ne, opts, edge);
+        let orig = |i: usize| if i < ne { i } else { i - ne };
+        let wword = |i: usize| weight.map_or(0, |w| w(orig(i)).to_word());
+
+        let (ncnt, plan) = place::count(numv, n, &edge, &wword, opts);
+        let ne: usize = ncnt.iter().sum();
+        if !index::fits::<I, O>(numv, ne) {
+            return Err(too_large(numv, ne));
+        }
 
         let mut g = CSR {
-            v: v,
-            e: el.chunks(2).len(),
-            vtxprop: vec![0f64; v],
-            offsets: vec![0; v],
-            neighbs: vec![0; el.chunks(2).len()],
+            v: numv,
+            e: ne,
+            vtxprop,
+            offsets: vec![O::default(); numv],
+            neighbs: vec![I::default(); ne],
+            weights: None,
+            in_edges: None,
+            sorted: false,
+            symmetric: opts.symmetrize,
         };
+        place::offsets_into(&ncnt, &mut g.offsets);
 
-        /* CSR Structure e.g.,
-          |0,3,5,6,9|
-          |v2,v3,v5|v1,v9|v2|v3,v7,v8|x|
-        */
-        /*vertex i's offset is vtx i-1's offset + i's neighbor count*/
-        for i in 1..ncnt.len() {
-            g.offsets[i] = g.offsets[i - 1] + ncnt[i - 1].load(Ordering::SeqCst);
-            work_offsets.push(AtomicUsize::new(g.offsets[i]));
+        /*Weights are placed as 8-byte words, then turned into W*/
+        let mut wts = vec![0u64; if weight.is_some() { ne } else { 0 }];
+        place::place(plan, numv, n, &edge, &wword, &g.offsets, &mut g.neighbs, &mut wts);
+        if weight.is_some() {
+            g.weights = Some(wts.into_par_iter().map(W::from_word).collect());
         }
 
-        /*Temporary synchronized edge list array*/
-        let mut nbs = Vec::with_capacity(el.chunks(2).len());
-        for _ in 0..el.chunks(2).len() {
-            nbs.push(AtomicUsize::new(0));
+        if opts.sort || opts.dedup {
+            g.sort_neighbors(opts.dedup);
         }
 
-        /*Populate the neighbor array based on the counts*/
-        el.chunks(2).par_bridge().for_each(|e| {
-            let cur_ind = work_offsets[e[0]].fetch_add(1, Ordering::SeqCst);
-            nbs[cur_ind].store(e[1], Ordering::Relaxed);
-        });
+        /*return the graph, g*/
+        Ok(g)
+    }
 
-        g.neighbs.par_iter_mut().enumerate().for_each(|(i,e)| {
-            *e = nbs[i].load(Ordering::Relaxed);
-        });
+    /*Sort each vertex's neighbors, carrying their weights along, in
+    parallel.  With dedup, also drop repeated neighbors, keeping the
+    smallest weight of each, and compact the neighbs array.*/
+    fn sort_neighbors(&mut self, dedup: bool) {
+        let mut degs: Vec<usize> = (0..self.v).map(|u| self.out_degree(u)).collect();
+        let wts = self.weights.as_deref_mut().unwrap_or(&mut []);
+        place::sort_segments(&mut degs, &mut self.neighbs, wts, dedup);
+        self.sorted = true;
 
-        /*return the graph, g*/
-        g
+        let ne: usize = degs.iter().sum();
+        if ne == self.e {
+            return;
+        }
 
+        /*Squeeze out the dropped duplicates, keeping the first deg entries
+        of each vertex's old range*/
+        let starts: Vec<usize> = self.offsets.iter().map(|o| o.index()).collect();
+        place::offsets_into(&degs, &mut self.offsets);
+        let neighbs = &self.neighbs;
+        self.neighbs = (0..self.v)
+            .into_par_iter()
+            .flat_map_iter(|u| neighbs[starts[u]..starts[u] + degs[u]].iter().cloned())
+            .collect();
+        if let Some(weights) = &self.weights {
+            self.weights = Some(
+                (0..self.v)
+                    .into_par_iter()
+                    .flat_map_iter(|u| weights[starts[u]..starts[u] + degs[u]].iter().cloned())
+                    .collect(),
+            );
+        }
+        self.e = ne;
+    }
 
+    /*The source vertex of every edge, parallel to the neighbs array*/
+    fn sources(&self) -> Vec<usize> {
+        (0..self.v)
+            .into_par_iter()
+            .flat_map_iter(|v| {
+                let (st, en) = self.vtx_offset_range(v);
+                std::iter::repeat_n(v, en - st)
+            })
+            .collect()
     }
 
-    /// Take an edge list in and produce a CSR out
-    /// (u,v)
-    pub fn new(numv: usize, ref el: Vec<(usize, usize)>) -> CSR {
-        const NUMCHUNKS: usize = 16;
-        let chunksz: usize = if numv > NUMCHUNKS {
-            numv / NUMCHUNKS
-        } else {
-            1
+    /*Build the graph with every edge reversed, using the same parallel
+    counting and scattering as the constructors.  Edges are visited in
+    order of their source, so a deterministic build leaves each vertex's
+    in-neighbors sorted.*/
+    fn reversed<Q: PropTrait>(&self, vtxprop: Vec<Q>) -> CSR<Q, W, I, O> {
+        let src = self.sources();
+        let edge = |i: usize| (self.neighbs[i].index(), src[i]);
+        let opts = BuildOptions {
+            deterministic: true,
+            ..Default::default()
+        };
+        let t = match &self.weights {
+            Some(wts) => {
+                let weight = |i: usize| wts[i];
+                CSR::build(self.v, self.e, edge, Some(&weight), vtxprop, &opts)
+            }
+            None => CSR::build(self.v, self.e, edge, None, vtxprop, &opts),
         };
+        let mut t = t.expect("the transpose has the same counts as the graph");
+        t.sorted = true;
+        t.symmetric = self.symmetric;
+        t
+    }
 
-        /*TODO: Parameter*/
-        let numbins = 16;
+    /// Build the transpose of the graph (its CSC form), in which vertex v's
+    /// neighbors are the vertices with an edge to v in this graph.  Edge
+    /// weights and vertex properties are carried over.  If the graph keeps
+    /// its in-edges the transpose is copied out of them rather than rebuilt,
+    /// and keeps this graph's out-edges as its in-edges.
+    pub fn transpose(&self) -> CSR<P, W, I, O> {
+        match &self.in_edges {
+            Some(t) => CSR {
+                v: self.v,
+                e: self.e,
+                vtxprop: self.vtxprop.clone(),
+                offsets: t.offsets.clone(),
+                neighbs: t.neighbs.clone(),
+                weights: t.weights.clone(),
+                sorted: t.sorted,
+                symmetric: self.symmetric,
+                in_edges: Some(Box::new(CSR {
+                    v: self.v,
+                    e: self.e,
+                    vtxprop: vec![(); self.v],
+                    offsets: self.offsets.clone(),
+                    neighbs: self.neighbs.clone(),
+                    weights: self.weights.clone(),
+                    in_edges: None,
+                    sorted: self.sorted,
+                    symmetric: self.symmetric,
+                })),
+            },
+            None => self.reversed(self.vtxprop.clone()),
+        }
+    }
 
-        let mut ncnt = Vec::new();
-        for _ in 0..numv {
-            ncnt.push(AtomicUsize::new(0));
+    /// Build the transpose of the graph and keep it alongside the graph, so
+    /// in_neighbors and in_degree can be answered and write_fastcsr stores
+    /// both directions in one file
+    pub fn with_in_edges(mut self) -> CSR<P, W, I, O> {
+        if self.in_edges.is_none() {
+            self.in_edges = Some(Box::new(self.reversed(vec![(); self.v])));
         }
+        self
+    }
 
-        /*Count up the number of neighbors that each vertex has */
-        el.par_chunks(chunksz).for_each(|cnk| {
-            /*Per-thread bin structure*/
-            let mut bins = Vec::new();
-            for _ in 0..numbins {
-                bins.push(Vec::<&(usize, usize)>::new());
-            }
+    /// True if the graph keeps its in-edges (see with_in_edges)
+    pub fn has_in_edges(&self) -> bool {
+        self.in_edges.is_some()
+    }
 
-            /*iterate over chunk, push edges to bins*/
-            cnk.iter().for_each(|e| {
-                bins[(e).0 % 16].push(e);
-            });
+    fn in_edges(&self) -> &CSR<(), W, I, O> {
+        self.in_edges
+            .as_ref()
+            .expect("CSR has no in-edges, build them with with_in_edges")
+    }
 
-            bins.iter().for_each(|b| {
-                b.iter().for_each(|e| {
-                    ncnt[(e).0].fetch_add(1, Ordering::SeqCst);
-                });
-            });
-        });
+    /// The vertices with an edge to vertex v.  Panics if the graph does not
+    /// keep its in-edges.
+    pub fn in_neighbors(&self, v: usize) -> &[I] {
+        let t = self.in_edges();
+        let (st, en) = t.vtx_offset_range(v);
+        &t.neighbs[st..en]
+    }
 
-        let mut work_offsets = Vec::new();
-        work_offsets.push(AtomicUsize::new(0));
+    /// The vertices with an edge to vertex v and the weights of those edges.
+    /// Panics if the graph is unweighted or does not keep its in-edges.
+    pub fn weighted_in_neighbors(&self, v: usize) -> (&[I], &[W]) {
+        self.in_edges().weighted_neighbors(v)
+    }
 
-        let mut g = CSR {
-            v: numv,
-            e: el.len(),
-            vtxprop: vec![0f64; numv],
-            offsets: vec![0; numv],
-            neighbs: vec![0; el.len()],
-        };
+    /// The number of edges into vertex v.  Panics if the graph does not keep
+    /// its in-edges.
+    pub fn in_degree(&self, v: usize) -> usize {
+        self.in_neighbors(v).len()
+    }
+
+    /// The number of edges out of vertex v
+    pub fn out_degree(&self, v: usize) -> usize {
+        let (st, en) = self.vtx_offset_range(v);
+        en - st
+    }
 
-        /* CSR Structure e.g.,
-          |0,3,5,6,9|
-          |v2,v3,v5|v1,v9|v2|v3,v7,v8|x|
-        */
-        /*vertex i's offset is vtx i-1's offset + i's neighbor count*/
-        for i in 1..ncnt.len() {
-            g.offsets[i] = g.offsets[i - 1] + ncnt[i - 1].load(Ordering::SeqCst);
-            work_offsets.push(AtomicUsize::new(g.offsets[i]));
+    /// True if there is an edge from v0 to v1.  This is a binary search if
+    /// the graph was built with sorted neighbors (BuildOptions::sort), and a
+    /// scan of v0's neighbors otherwise.
+    pub fn has_edge(&self, v0: usize, v1: usize) -> bool {
+        if !I::fits(v1) {
+            return false;
+        }
+        let v1 = I::from_usize(v1);
+        let (st, en) = self.vtx_offset_range(v0);
+        let nbs = &self.neighbs[st..en];
+        match self.sorted {
+            true => nbs.binary_search(&v1).is_ok(),
+            false => nbs.contains(&v1),
         }
+    }
+
+    pub fn get_vtxprop(&self) -> &[P] {
+        &self.vtxprop
+    }
 
-        /*Temporary synchronized edge list array*/
-        let mut nbs = Vec::new();
-        for _ in 0..el.len() {
-            nbs.push(AtomicUsize::new(0));
+    pub fn get_mut_vtxprop(&mut self) -> &mut [P] {
+        &mut self.vtxprop
+    }
+
+    /// Swap the vertex property array for one of a different type, with every
+    /// vertex's property set to init, e.g., csr.with_vtxprop(u32::MAX) to
+    /// keep BFS depths or csr.with_vtxprop(0usize) to keep component labels
+    pub fn with_vtxprop<Q: PropTrait>(self, init: Q) -> CSR<Q, W, I, O> {
+        CSR {
+            v: self.v,
+            e: self.e,
+            vtxprop: vec![init; self.v],
+            offsets: self.offsets,
+            neighbs: self.neighbs,
+            weights: self.weights,
+            in_edges: self.in_edges,
+            sorted: self.sorted,
+            symmetric: self.symmetric,
         }
+    }
 
-        /*Populate the neighbor array based on the counts*/
-        el.par_chunks(chunksz).for_each(|cnk| {
-            cnk.iter().for_each(|edge| match *edge {
-                (v0, v1) => {
-                    let cur_ind = work_offsets[v0].fetch_add(1, Ordering::SeqCst);
-                    nbs[cur_ind].store(v1, Ordering::Relaxed);
-                }
-            });
-        });
+    /// Convert the graph to store its ids as J and offsets as Q, e.g.,
+    /// csr.with_index::<u32, u32>() to get a CompactCSR.  Fails with TooLarge
+    /// if the graph has too many vertices or edges for them.
+    pub fn with_index<J: Idx, Q: Idx>(self) -> Result<CSR<P, W, J, Q>> {
+        if !index::fits::<J, Q>(self.v, self.e) {
+            return Err(too_large(self.v, self.e));
+        }
+        let in_edges = match self.in_edges {
+            Some(t) => Some(Box::new(t.with_index::<J, Q>()?)),
+            None => None,
+        };
+        Ok(CSR {
+            v: self.v,
+            e: self.e,
+            vtxprop: self.vtxprop,
+            offsets: self.offsets.par_iter().map(|o| Q::from_usize(o.index())).collect(),
+            neighbs: self.neighbs.par_iter().map(|n| J::from_usize(n.index())).collect(),
+            weights: self.weights,
+            in_edges,
+            sorted: self.sorted,
+            symmetric: self.symmetric,
+        })
+    }
 
-        g.neighbs
-            .par_chunks_mut(chunksz)
-            .enumerate()
-            .for_each(|(chunkbase, cnk)| {
-                cnk.iter_mut().enumerate().for_each(|(i, e)| {
-                    *e = nbs[chunkbase + i].load(Ordering::Relaxed);
-                });
-            });
+    pub fn get_v(&self) -> usize {
+        self.v
+    }
 
-        /*return the graph, g*/
-        g
+    pub fn get_e(&self) -> usize {
+        self.e
+    }
+
+    pub fn get_offsets(&self) -> &Vec<O> {
+        &self.offsets
+    }
+
+    pub fn get_neighbs(&self) -> &[I] {
+        &self.neighbs
+    }
+
+    /// The weight of each edge, parallel to get_neighbs, if the graph is weighted
+    pub fn get_weights(&self) -> Option<&[W]> {
+        self.weights.as_deref()
+    }
+
+    pub fn is_weighted(&self) -> bool {
+        self.weights.is_some()
     }
 
     /// Get the range of offsets into the neighbs array that hold the neighbors
     /// of vertex v
     pub fn vtx_offset_range(&self, v: usize) -> (usize, usize) {
         (
-            self.offsets[v],
+            self.offsets[v].index(),
             match v {
                 v if v == self.v - 1 => self.e,
-                _ => self.offsets[v + 1],
+                _ => self.offsets[v + 1].index(),
             },
         )
     }
 
+    /// The neighbors of vertex v and the weights of the edges to them.
+    /// Panics if the graph is unweighted.
+    pub fn weighted_neighbors(&self, v: usize) -> (&[I], &[W]) {
+        let (st, en) = self.vtx_offset_range(v);
+        let weights = self.weights.as_ref().expect("CSR is not weighted");
+        (&self.neighbs[st..en], &weights[st..en])
+    }
+
     /// read_only_scan is a read only scan of all edges in the entire CSR
     /// that accepts a FnMut(usize,usize,u64) -> () to apply to each vertex
-    pub fn read_only_scan(&self, mut f: impl FnMut(usize, usize) -> ()) {
+    pub fn read_only_scan(&self, mut f: impl FnMut(usize, usize)) {
         /*Iterate over the vertices in the offsets array*/
         let len = self.offsets.len();
         for i in 0..len {
@@ -306,54 +815,306 @@ impl CSR {
             let (i_start, i_end) = self.vtx_offset_range(i);
             /*Traverse vertex i's neighbs and call provided f(...) on the edge*/
             for ei in i_start..i_end {
-                let e = self.neighbs[ei];
-                match e {
-                    v1 => {
-                        f(i, v1);
+                f(i, self.neighbs[ei].index());
+            }
+        }
+    }
+
+    /// Like read_only_scan, but f also gets each edge's weight.
+    /// Panics if the graph is unweighted.
+    pub fn read_only_scan_weighted(&self, mut f: impl FnMut(usize, usize, W)) {
+        for i in 0..self.v {
+            let (nbs, wts) = self.weighted_neighbors(i);
+            nbs.iter().zip(wts).for_each(|(n, w)| f(i, n.index(), *w));
+        }
+    }
+
+    /// Apply f to every vertex in parallel, handing it the vertex's neighbors
+    /// and the weights of the edges to them.  Panics if the graph is unweighted.
+    pub fn neighbor_scan_weighted(&self, f: impl Fn(usize, &[I], &[W]) + Sync) {
+        (0..self.v).into_par_iter().for_each(|v| {
+            let (nbs, wts) = self.weighted_neighbors(v);
+            f(v, nbs, wts);
+        });
+    }
+
+    /// True if the graph was built with BuildOptions::symmetrize, so every
+    /// edge (v0,v1) has a matching (v1,v0)
+    pub fn is_symmetric(&self) -> bool {
+        self.symmetric
+    }
+
+    /// True if every vertex's neighbors are listed in increasing order
+    pub fn is_sorted(&self) -> bool {
+        self.sorted || (0..self.v).into_par_iter().all(|v| {
+            let (st, en) = self.vtx_offset_range(v);
+            self.neighbs[st..en].windows(2).all(|w| w[0] <= w[1])
+        })
+    }
+
+    /// Write the CSR out in the version 2 FastCSR format (see format.rs)
+    /// so that it can be loaded with FastCSR::new.  Ids and offsets are
+    /// written in the graph's own widths (I and O), which the header records.
+    /// Weights, if there are any, are written as a weights section of type W,
+    /// and in-edges, if the graph keeps them, as a second set of sections.
+    /// The file is marked directed unless the graph was built with
+    /// symmetrize.
+    ///
+    /// The sections are streamed from the CSR's own arrays through a
+    /// buffered writer into a temporary file next to s, which is synced and
+    /// renamed to s only once it is complete, so a reader never sees a
+    /// half-written graph.
+    pub fn write_fastcsr(&self, s: String) -> Result<()> {
+        let out = AtomicFile::new(s);
+        let file = self.write_fastcsr_to(&out)?;
+        out.commit(file)
+    }
+
+    /*Write the FastCSR file into out's temporary file, leaving the caller
+    to commit it*/
+    pub(crate) fn write_fastcsr_to(&self, out: &AtomicFile) -> Result<File> {
+        let mut flags = 0;
+        if !self.symmetric {
+            flags |= format::FLAG_DIRECTED;
+        }
+        if self.is_sorted() {
+            flags |= format::FLAG_SORTED;
+        }
+        let mut h = match self.weights {
+            Some(_) => Header::new_weighted::<W>(self.v, self.e, flags),
+            None => Header::new(self.v, self.e, flags),
+        }
+        .with_widths(I::WIDTH, O::WIDTH);
+        if self.in_edges.is_some() {
+            h = h.with_in_edges();
+        }
+        let len = h.file_len().ok_or(FormatError::TooLarge {
+            v: self.v,
+            e: self.e,
+        })?;
+
+        out.write(|w| {
+            /*Write each section where the header says it starts, padding the
+            gaps between sections with zeros*/
+            let mut at = 0;
+            let mut put = |off: usize, bytes: &[u8]| -> Result<()> {
+                w.write_all(&vec![0u8; off - at])?;
+                w.write_all(bytes)?;
+                at = off + bytes.len();
+                Ok(())
+            };
+            put(0, &h.to_bytes())?;
+            put(h.offsets_off, self.offsets.as_byte_slice())?;
+            put(h.neighbs_off, self.neighbs.as_byte_slice())?;
+            if let Some(wts) = &self.weights {
+                put(h.weights_off, wts.as_byte_slice())?;
+            }
+            if let Some(t) = &self.in_edges {
+                put(h.in_offsets_off, t.offsets.as_byte_slice())?;
+                put(h.in_neighbs_off, t.neighbs.as_byte_slice())?;
+                if let Some(wts) = &t.weights {
+                    put(h.in_weights_off, wts.as_byte_slice())?;
+                }
+            }
+            put(len, &[])
+        })
+    }
+
+    /// Write the graph out as a general Matrix Market coordinate file, with
+    /// 1-based ids and one entry per edge.  A weighted graph is written as
+    /// a real or integer matrix, depending on W, and an unweighted one as a
+    /// pattern matrix.
+    pub fn write_mtx(&self, s: String) -> Result<()> {
+        let h = mtx::MtxHeader {
+            rows: self.v,
+            cols: self.v,
+            entries: self.e,
+            field: match self.weights {
+                Some(_) => mtx::Field::of::<W>(),
+                None => mtx::Field::Pattern,
+            },
+            symmetry: mtx::Symmetry::General,
+        };
+
+        format::write_file(s, |w| {
+            writeln!(w, "{}", h.banner())?;
+            writeln!(w, "{} {} {}", h.rows, h.cols, h.entries)?;
+            for v in 0..self.v {
+                let (st, en) = self.vtx_offset_range(v);
+                for i in st..en {
+                    match &self.weights {
+                        Some(wts) => writeln!(w, "{} {} {}", v + 1, self.neighbs[i].index() + 1, wts[i])?,
+                        None => writeln!(w, "{} {}", v + 1, self.neighbs[i].index() + 1)?,
                     }
                 }
             }
+            Ok(())
+        })
+    }
+
+    /// Write the graph out as a METIS .graph file (see metis.rs), with the
+    /// weights, if it has any, as edge weights.  METIS graphs are
+    /// undirected, so the graph has to be symmetric, with every edge (v0,v1)
+    /// matched by an edge (v1,v0), as many times as it appears, and have no
+    /// self-loops; each pair counts as one edge in the header.  Fails with
+    /// an InvalidInput i/o error if the graph is not like that.
+    pub fn write_metis(&self, s: String) -> Result<()> {
+        let not_undirected = |v: usize, n: usize| {
+            Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("METIS graphs are undirected, but edge ({},{}) is not", v, n),
+            )))
+        };
+
+        /*Each pair's count goes up for its edges one way and down for its
+        edges the other way, so a pair left nonzero is not matched edge for
+        edge*/
+        let mut balance: HashMap<(usize, usize), isize> = HashMap::new();
+        for v in 0..self.v {
+            let (st, en) = self.vtx_offset_range(v);
+            for n in &self.neighbs[st..en] {
+                let n = n.index();
+                match v.cmp(&n) {
+                    Ordering::Equal => return not_undirected(v, n),
+                    Ordering::Less => *balance.entry((v, n)).or_insert(0) += 1,
+                    Ordering::Greater => *balance.entry((n, v)).or_insert(0) -= 1,
+                }
+            }
+        }
+        if let Some(((v, n), _)) = balance.iter().filter(|(_, b)| **b != 0).min() {
+            return not_undirected(*v, *n);
         }
+
+        let h = metis::MetisHeader {
+            v: self.v,
+            edges: self.e / 2,
+            vertex_sizes: false,
+            vertex_weights: 0,
+            edge_weights: self.weights.is_some(),
+        };
+        format::write_file(s, |w| {
+            writeln!(w, "{}", h.line())?;
+            for v in 0..self.v {
+                let (st, en) = self.vtx_offset_range(v);
+                let adj: Vec<String> = (st..en)
+                    .map(|i| match &self.weights {
+                        Some(wts) => format!("{} {}", self.neighbs[i].index() + 1, wts[i]),
+                        None => format!("{}", self.neighbs[i].index() + 1),
+                    })
+                    .collect();
+                writeln!(w, "{}", adj.join(" "))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Write the graph out as a DIMACS shortest path .gr file (see
+    /// dimacs.rs), with one arc per edge.  An unweighted graph's arcs all
+    /// have weight 1.
+    pub fn write_dimacs(&self, s: String) -> Result<()> {
+        format::write_file(s, |w| {
+            writeln!(w, "p sp {} {}", self.v, self.e)?;
+            for v in 0..self.v {
+                let (st, en) = self.vtx_offset_range(v);
+                for i in st..en {
+                    match &self.weights {
+                        Some(wts) => writeln!(w, "a {} {} {}", v + 1, self.neighbs[i].index() + 1, wts[i])?,
+                        None => writeln!(w, "a {} {} 1", v + 1, self.neighbs[i].index() + 1)?,
+                    }
+                }
+            }
+            Ok(())
+        })
     }
 
-    pub fn write_fastcsr(&self, s: String) {
-        let path = PathBuf::from(s);
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(&path)
-            .unwrap();
-        file.set_len((self.offsets.len() + self.neighbs.len() + 2) as u64 * 8)
-            .unwrap();
-
-        let mmap = unsafe { MmapMut::map_mut(&file) };
-
-        let offsets_bytes = unsafe { self.offsets.align_to::<u8>().1 };
-        let neighbs_bytes = unsafe { self.neighbs.align_to::<u8>().1 };
-        mmap.unwrap().copy_from_slice(
-            &[
-                &self.offsets.len().to_le_bytes(),
-                &self.neighbs.len().to_le_bytes(),
-                offsets_bytes,
-                neighbs_bytes,
-            ]
-            .concat(),
-        );
+    /// Write the graph out as a Ligra AdjacencyGraph file (see ligra.rs), or
+    /// a WeightedAdjacencyGraph file if it is weighted
+    pub fn write_ligra(&self, s: String) -> Result<()> {
+        format::write_file(s, |w| {
+            match self.weights {
+                Some(_) => writeln!(w, "{}", ligra::WEIGHTED_HEADER)?,
+                None => writeln!(w, "{}", ligra::HEADER)?,
+            }
+            writeln!(w, "{}\n{}", self.v, self.e)?;
+            for v in 0..self.v {
+                writeln!(w, "{}", self.vtx_offset_range(v).0)?;
+            }
+            for n in &self.neighbs {
+                writeln!(w, "{}", n)?;
+            }
+            if let Some(wts) = &self.weights {
+                for x in wts {
+                    writeln!(w, "{}", x)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Write the graph out as a Galois .gr file (see galois.rs): version 1,
+    /// with 4-byte ids, if every id fits in 4 bytes, else version 2.  The
+    /// weights, if the graph has any, are written as the edge data.
+    pub fn write_galois(&self, s: String) -> Result<()> {
+        let (version, iw) = match self.v == 0 || u32::fits(self.v - 1) {
+            true => (1u64, 4),
+            false => (2u64, 8),
+        };
+        let edge_size = match self.weights {
+            Some(_) => std::mem::size_of::<W>(),
+            None => 0,
+        };
+        let wbase = galois::edge_data_off(self.v, self.e, iw).ok_or(FormatError::TooLarge {
+            v: self.v,
+            e: self.e,
+        })?;
+
+        format::write_file(s, |w| {
+            let header = [version, edge_size as u64, self.v as u64, self.e as u64];
+            w.write_all(header.as_byte_slice())?;
+            let ends: Vec<u64> = (0..self.v).map(|v| self.vtx_offset_range(v).1 as u64).collect();
+            w.write_all(ends.as_byte_slice())?;
+            match iw {
+                4 => {
+                    let outs: Vec<u32> = self.neighbs.iter().map(|n| n.index() as u32).collect();
+                    w.write_all(outs.as_byte_slice())?;
+                }
+                _ => {
+                    let outs: Vec<u64> = self.neighbs.iter().map(|n| n.index() as u64).collect();
+                    w.write_all(outs.as_byte_slice())?;
+                }
+            }
+            let at = 32 + 8 * self.v + iw * self.e;
+            w.write_all(&vec![0u8; wbase - at])?;
+            if let Some(wts) = &self.weights {
+                w.write_all(wts.as_byte_slice())?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Write the graph out as a compressed FastCSR file (see compressed.rs),
+    /// to be loaded with CompressedFastCSR::open.  Each vertex's neighbors
+    /// are written sorted and gap encoded; weights and in-edges are not
+    /// written.
+    pub fn write_compressed_fastcsr(&self, s: String) -> Result<()> {
+        compressed::write(PathBuf::from(s), self.v, !self.symmetric, |u, buf| {
+            let (st, en) = self.vtx_offset_range(u);
+            buf.extend(self.neighbs[st..en].iter().map(|n| n.index()));
+        })?;
+        Ok(())
     }
 
     /// bfs_traversal starts from vertex start and does a breadth first search
     /// traversal on the vertices, applying f, the closure passed in, to each
     /// vertex
-    pub fn bfs_traversal(&self, start: usize, mut f: impl FnMut(usize) -> ()) {
+    pub fn bfs_traversal(&self, start: usize, mut f: impl FnMut(usize)) {
         let mut visited = BitVec::from_elem(self.v, false);
-        let mut q = Vec::new();
+        let mut q = VecDeque::new();
 
         visited.set(start, true);
-        q.push(start);
+        q.push_back(start);
 
-        while q.len() > 0 {
-            let v = q.remove(0);
+        while let Some(v) = q.pop_front() {
 
             f(v);
 
@@ -361,47 +1122,87 @@ impl CSR {
 
             for nei in st..en {
                 /*Get the first element of the edge, which is the distal vertex*/
-                let ne = self.neighbs[nei] as usize;
+                let ne = self.neighbs[nei].index();
 
-                match visited[ne] {
-                    false => {
-                        visited.set(ne, true);
-                        q.push(ne as usize);
-                    }
-                    _ => (),
+                if !visited[ne] {
+                    visited.set(ne, true);
+                    q.push_back(ne);
                 }
             }
         }
     }
 
+    /// Parallel breadth first search from vertex start, returning each
+    /// vertex's depth and parent (see bfs.rs).  Panics if start is not a
+    /// vertex.
+    pub fn par_bfs(&self, start: usize) -> BfsTree {
+        bfs::bfs(self, start)
+    }
+
+    /// Direction-optimizing parallel breadth first search from vertex
+    /// start, returning the search tree and the direction each level was
+    /// expanded in (see bfs.rs).  Panics if start is not a vertex or the
+    /// graph does not keep its in-edges.
+    pub fn par_do_bfs(&self, start: usize, opts: &DirectionOptions) -> (BfsTree, Vec<Direction>) {
+        bfs::direction_optimizing_bfs(self, start, opts)
+    }
+
+    /// Depth first search from vertex start, calling vis on each vertex
+    /// and edge as it goes (see dfs.rs).  Panics if start is not a vertex.
+    pub fn dfs(&self, start: usize, vis: &mut impl DfsVisitor) {
+        dfs::dfs(self, start, vis)
+    }
+
+    /// Depth first search of the whole graph, starting a new tree at each
+    /// vertex, in id order, not yet reached
+    pub fn dfs_all(&self, vis: &mut impl DfsVisitor) {
+        dfs::dfs_all(self, vis)
+    }
+
     pub fn par_scan(
         &mut self,
         par_level: usize,
-        f: impl Fn(usize, &[usize]) -> f64 + std::marker::Sync,
-    ) -> () {
+        f: impl Fn(usize, &[I]) -> P + std::marker::Sync,
+    ) {
         /*basically the number of threads to use*/
         let chunksz: usize = if self.v > par_level {
             self.v / par_level
         } else {
             1
         };
-        let scan_vtx_row = |(row_i, vtx_row): (usize, &mut [f64])| {
+        let scan_vtx_row = |(row_i, vtx_row): (usize, &mut [P])| {
             let row_i_base: usize = row_i * chunksz;
             vtx_row
                 .iter_mut()
                 .enumerate()
-                .for_each(|(ii, v): (usize, &mut f64)| {
+                .for_each(|(ii, v): (usize, &mut P)| {
                     let v0 = row_i_base + ii;
                     let (start, end) = self.vtx_offset_range(v0);
                     *v = f(v0, &self.neighbs[start..end]);
                 });
         };
 
-        let mut vtxprop = vec![0.0; self.get_v()];
+        let mut vtxprop = self.vtxprop.clone();
         vtxprop
             .par_chunks_mut(chunksz)
             .enumerate()
             .for_each(scan_vtx_row);
-        self.vtxprop.copy_from_slice(&vtxprop);
+        self.vtxprop = vtxprop;
+    }
+
+    /// Like par_scan, but f also gets the weights of the edges to each
+    /// vertex's neighbors.  Panics if the graph is unweighted.
+    pub fn par_scan_weighted(
+        &mut self,
+        f: impl Fn(usize, &[I], &[W]) -> P + std::marker::Sync,
+    ) {
+        let vtxprop = (0..self.v)
+            .into_par_iter()
+            .map(|v| {
+                let (nbs, wts) = self.weighted_neighbors(v);
+                f(v, nbs, wts)
+            })
+            .collect();
+        self.vtxprop = vtxprop;
     }
 } /*impl CSR*/
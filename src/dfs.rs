@@ -0,0 +1,124 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  Depth first search with an explicit stack, so a path through millions of
+  vertices cannot overflow the call stack.  Each stack entry is a vertex and
+  the position of the next of its neighbors to look at.  Vertices go from
+  undiscovered to open (on the stack) to finished, and each edge is
+  classified by the state of its target when it is looked at: undiscovered
+  makes it a tree edge, open a back edge, and finished a forward or cross
+  edge.
+*/
+
+use crate::index::Idx;
+use crate::traverse::Adjacency;
+
+/// What a depth first search calls as it goes.  Every method does nothing
+/// by default, so a visitor only implements the events it needs.
+pub trait DfsVisitor {
+    /// v is the root of a new search tree
+    fn root(&mut self, _v: usize) {}
+
+    /// v is seen for the first time (preorder)
+    fn discover(&mut self, _v: usize) {}
+
+    /// All of v's edges have been looked at (postorder)
+    fn finish(&mut self, _v: usize) {}
+
+    /// The edge (u,v) discovers v
+    fn tree_edge(&mut self, _u: usize, _v: usize) {}
+
+    /// The edge (u,v) goes to v, which is still open: an ancestor of u, or
+    /// u itself.  A directed graph has a cycle if and only if a search finds
+    /// a back edge.
+    fn back_edge(&mut self, _u: usize, _v: usize) {}
+
+    /// The edge (u,v) goes to v, which is already finished
+    fn forward_or_cross_edge(&mut self, _u: usize, _v: usize) {}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Undiscovered,
+    Open,
+    Finished,
+}
+
+/*The state of every vertex, kept across the trees of dfs_all*/
+struct Search {
+    state: Vec<State>,
+    stack: Vec<(usize, usize)>,
+}
+
+impl Search {
+    fn new(v: usize) -> Search {
+        Search {
+            state: vec![State::Undiscovered; v],
+            stack: Vec::new(),
+        }
+    }
+
+    /*Search the tree rooted at the undiscovered vertex start*/
+    fn tree<G: Adjacency + ?Sized>(&mut self, g: &G, start: usize, vis: &mut impl DfsVisitor) {
+        vis.root(start);
+        vis.discover(start);
+        self.state[start] = State::Open;
+        self.stack.push((start, 0));
+
+        while let Some(top) = self.stack.last_mut() {
+            let (u, i) = *top;
+            let nbs = g.out_neighbors(u);
+            if i == nbs.len() {
+                self.stack.pop();
+                self.state[u] = State::Finished;
+                vis.finish(u);
+                continue;
+            }
+            top.1 += 1;
+
+            let v = nbs[i].index();
+            match self.state[v] {
+                State::Undiscovered => {
+                    vis.tree_edge(u, v);
+                    vis.discover(v);
+                    self.state[v] = State::Open;
+                    self.stack.push((v, 0));
+                }
+                State::Open => vis.back_edge(u, v),
+                State::Finished => vis.forward_or_cross_edge(u, v),
+            }
+        }
+    }
+}
+
+/// Depth first search of g from vertex start, calling vis as it goes.
+/// Neighbors are explored in the order the graph stores them.  Panics if
+/// start is not a vertex of g.
+pub fn dfs<G: Adjacency + ?Sized>(g: &G, start: usize, vis: &mut impl DfsVisitor) {
+    assert!(start < g.num_v(), "start vertex {} of a graph with {} vertices", start, g.num_v());
+    Search::new(g.num_v()).tree(g, start, vis);
+}
+
+/// Depth first search of all of g: a search from each vertex, in id order,
+/// that no earlier search reached.  Every vertex is discovered and finished
+/// once and every edge is classified once, so the reverse of the finish
+/// order is a topological order if no back edge is found.
+pub fn dfs_all<G: Adjacency + ?Sized>(g: &G, vis: &mut impl DfsVisitor) {
+    let mut s = Search::new(g.num_v());
+    for v in 0..g.num_v() {
+        if s.state[v] == State::Undiscovered {
+            s.tree(g, v, vis);
+        }
+    }
+}
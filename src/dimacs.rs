@@ -0,0 +1,109 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  DIMACS shortest path .gr files, as used by the 9th DIMACS implementation
+  challenge:
+
+  c any number of comment lines
+  p sp <n> <m>
+  a <u> <v> <w>
+  ...
+
+  with one "a" line per directed arc from u to v of weight w, and ids
+  1-based.
+*/
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::csr::{EdgeList, WeightedEdgeList};
+use crate::error::{Error, Result};
+use crate::weight::Weight;
+
+/*Read the DIMACS file at path, handing f the line number, 0-based ids,
+and weight field of each arc.  Returns the number of vertices.*/
+fn read(path: &str, mut f: impl FnMut(u64, usize, usize, &str) -> Result<()>) -> Result<usize> {
+    let mut line = 0u64;
+    let mut problem: Option<(usize, usize)> = None;
+    let mut arcs = 0;
+    for l in BufReader::new(File::open(path)?).lines() {
+        line += 1;
+        let l = l?;
+        let words: Vec<&str> = l.split_whitespace().collect();
+        match words.first().copied() {
+            None | Some("c") => (),
+            Some("p") => {
+                let (n, m) = match (problem, &words[1..]) {
+                    (None, ["sp", n, m]) => (n.parse::<usize>(), m.parse::<usize>()),
+                    (None, _) => return Err(Error::parse(line, format!("bad problem line {:?}", l))),
+                    (Some(_), _) => return Err(Error::parse(line, "second problem line")),
+                };
+                match (n, m) {
+                    (Ok(n), Ok(m)) => problem = Some((n, m)),
+                    _ => return Err(Error::parse(line, format!("bad problem line {:?}", l))),
+                }
+            }
+            Some("a") => {
+                let (n, _) = problem.ok_or_else(|| Error::parse(line, "arc before the problem line"))?;
+                if words.len() != 4 {
+                    return Err(Error::parse(line, format!("expected 4 fields, found {}", words.len())));
+                }
+                let id = |w: &str| -> Result<usize> {
+                    let x = w
+                        .parse::<usize>()
+                        .map_err(|_| Error::parse(line, format!("bad vertex id {:?}", w)))?;
+                    match x {
+                        0 => Err(Error::parse(line, "vertex ids are 1-based")),
+                        x if x > n => Err(Error::VertexOutOfRange { vtx: x - 1, v: n }),
+                        x => Ok(x - 1),
+                    }
+                };
+                f(line, id(words[1])?, id(words[2])?, words[3])?;
+                arcs += 1;
+            }
+            Some(w) => return Err(Error::parse(line, format!("unknown line type {:?}", w))),
+        }
+    }
+
+    let (n, m) = problem.ok_or_else(|| Error::parse(line, "missing problem line"))?;
+    if arcs != m {
+        return Err(Error::parse(line, format!("expected {} arcs, found {}", m, arcs)));
+    }
+    Ok(n)
+}
+
+/// Read the arcs of the DIMACS file at path, ignoring their weights.
+/// Returns the number of vertices and the edge list.  Fails if the file
+/// cannot be read, a line does not parse, an id is out of range, or the
+/// file does not have as many arcs as its problem line says.
+pub fn read_el(path: &str) -> Result<(usize, EdgeList)> {
+    let mut el = Vec::new();
+    let v = read(path, |_, v0, v1, _| {
+        el.push((v0, v1));
+        Ok(())
+    })?;
+    Ok((v, el))
+}
+
+/// Like read_el, with the arc weights as weights of type W.  Fails if a
+/// weight does not parse as a W.
+pub fn read_wel<W: Weight>(path: &str) -> Result<(usize, WeightedEdgeList<W>)> {
+    let mut el = Vec::new();
+    let v = read(path, |line, v0, v1, s| {
+        let w = W::parse_weight(s).ok_or_else(|| Error::parse(line, format!("bad weight {:?}", s)))?;
+        el.push((v0, v1, w));
+        Ok(())
+    })?;
+    Ok((v, el))
+}
@@ -0,0 +1,204 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::fmt;
+use std::io;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The error type for everything in raphy that reads or writes a graph
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// Line line of a text input could not be parsed
+    Parse { line: u64, msg: String },
+    /// An edge names vertex vtx, but the graph only has v vertices
+    VertexOutOfRange { vtx: usize, v: usize },
+    /// A binary input does not have the layout it claims to have
+    Format(FormatError),
+}
+
+/// Describes which consistency check a binary graph file failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// The file is shorter than its header says it should be
+    Truncated { len: usize, need: usize },
+    /// The vertex and edge counts in the file are too large to address
+    TooLarge { v: usize, e: usize },
+    /// The file does not start with the magic number of its kind of file
+    BadMagic,
+    UnsupportedVersion(u64),
+    /// The file was written by a machine with the opposite byte order
+    ByteOrder,
+    /// The file stores ids or offsets in a width this build cannot read
+    IndexWidth { id: usize, offset: usize },
+    /// The flags name a kind of weight that raphy does not know
+    UnknownWeightKind(u64),
+    /// The flags say a section is present but the header does not locate it
+    MissingSection(&'static str),
+    /// The weights are not of the type the caller asked for
+    WeightKind { expected: u64, found: u64 },
+    /// The weights are found bytes wide, not the expected width of the
+    /// type the caller asked for
+    WeightWidth { expected: usize, found: usize },
+    /// A section does not start on an 8-byte boundary
+    Misaligned { section: &'static str, off: usize },
+    /// A section starts at byte off, inside the header
+    SectionInHeader { section: &'static str, off: usize },
+    /// Two sections share bytes of the file
+    SectionsOverlap { first: &'static str, second: &'static str },
+    /// A binary edge list is not a whole number of (v0,v1) pairs
+    EdgeListLength(usize),
+    /// offsets[0] is not 0
+    FirstOffsetNonZero(usize),
+    /// offsets[vtx] is smaller than offsets[vtx - 1]
+    OffsetsNotMonotonic { vtx: usize },
+    /// offsets[vtx] points past the end of the neighbs array
+    OffsetOutOfRange { vtx: usize, offset: usize, e: usize },
+    /// One of vtx's neighbors has an id that is not less than v
+    NeighborOutOfRange { vtx: usize, neighbor: usize, v: usize },
+    /// The file is compressed (true) or not (false), and the loader reads
+    /// the other kind
+    Compressed(bool),
+    /// The file's lists are coded by reference to other lists (true) or not
+    /// (false), and the loader reads the other kind
+    References(bool),
+    /// vtx's encoded neighbor list runs past the end of its bytes or does
+    /// not fill them
+    BadEncoding { vtx: usize },
+    /// The neighbor lists hold count edges, not the e the header says
+    EdgeCount { count: usize, e: usize },
+    /// Vertex vtx's label is not UTF-8, is out of order in the sorted
+    /// table, or repeats another vertex's label
+    BadLabel { vtx: usize },
+}
+
+impl Error {
+    pub(crate) fn parse(line: u64, msg: impl Into<String>) -> Error {
+        Error::Parse {
+            line,
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "i/o error: {}", e),
+            Error::Parse { line, msg } => write!(f, "line {}: {}", line, msg),
+            Error::VertexOutOfRange { vtx, v } => {
+                write!(f, "vertex {} is out of range for a graph of {} vertices", vtx, v)
+            }
+            Error::Format(e) => write!(f, "bad graph file: {}", e),
+        }
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Truncated { len, need } => {
+                write!(f, "file is {} bytes, expected at least {}", len, need)
+            }
+            FormatError::TooLarge { v, e } => {
+                write!(f, "{} vertices and {} edges are too many to address", v, e)
+            }
+            FormatError::BadMagic => write!(f, "missing magic number"),
+            FormatError::UnsupportedVersion(v) => write!(f, "unsupported FastCSR version {}", v),
+            FormatError::ByteOrder => write!(f, "file was written with the opposite byte order"),
+            FormatError::IndexWidth { id, offset } => write!(
+                f,
+                "unsupported index width: {}-byte ids, {}-byte offsets",
+                id, offset
+            ),
+            FormatError::UnknownWeightKind(k) => write!(f, "unknown weight kind {}", k),
+            FormatError::MissingSection(section) => write!(f, "{} section is missing", section),
+            FormatError::WeightKind { expected, found } => write!(
+                f,
+                "weights are of kind {}, expected kind {}",
+                found, expected
+            ),
+            FormatError::WeightWidth { expected, found } => write!(
+                f,
+                "weights are {} bytes wide, expected {}",
+                found, expected
+            ),
+            FormatError::Misaligned { section, off } => {
+                write!(f, "{} section at byte {} is not 8-byte aligned", section, off)
+            }
+            FormatError::SectionInHeader { section, off } => {
+                write!(f, "{} section at byte {} starts inside the header", section, off)
+            }
+            FormatError::SectionsOverlap { first, second } => {
+                write!(f, "{} and {} sections overlap", first, second)
+            }
+            FormatError::EdgeListLength(len) => {
+                write!(f, "edge list of {} bytes is not a whole number of edges", len)
+            }
+            FormatError::FirstOffsetNonZero(o) => write!(f, "offset of vertex 0 is {}, not 0", o),
+            FormatError::OffsetsNotMonotonic { vtx } => {
+                write!(f, "offset of vertex {} is less than the offset of vertex {}", vtx, vtx - 1)
+            }
+            FormatError::OffsetOutOfRange { vtx, offset, e } => {
+                write!(f, "offset {} of vertex {} exceeds edge count {}", offset, vtx, e)
+            }
+            FormatError::NeighborOutOfRange { vtx, neighbor, v } => write!(
+                f,
+                "vertex {} has neighbor {} but the graph has {} vertices",
+                vtx, neighbor, v
+            ),
+            FormatError::Compressed(true) => {
+                write!(f, "file is compressed, open it with CompressedFastCSR or BvGraph")
+            }
+            FormatError::Compressed(false) => write!(f, "file is not compressed"),
+            FormatError::References(true) => {
+                write!(f, "file is reference compressed, open it with BvGraph")
+            }
+            FormatError::References(false) => {
+                write!(f, "file is not reference compressed, open it with CompressedFastCSR")
+            }
+            FormatError::BadEncoding { vtx } => {
+                write!(f, "neighbor list of vertex {} is badly encoded", vtx)
+            }
+            FormatError::EdgeCount { count, e } => {
+                write!(f, "neighbor lists hold {} edges, expected {}", count, e)
+            }
+            FormatError::BadLabel { vtx } => write!(f, "label of vertex {} is bad or repeated", vtx),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Format(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<FormatError> for Error {
+    fn from(e: FormatError) -> Error {
+        Error::Format(e)
+    }
+}
@@ -0,0 +1,291 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  Export to GraphViz DOT and GraphML, for looking at small graphs in a
+  viewer instead of reading print output.  Vertices are named by their ids,
+  carry their values (a CSR's vtxprop, a Graph's vertex values, or any slice
+  of per-vertex results) as attributes, and can be highlighted along with a
+  set of edges, e.g., the edges of a BFS tree.
+*/
+
+use bit_vec::BitVec;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::csr::CSR;
+use crate::error::{Error, Result};
+use crate::fast_csr::FastCSR;
+use crate::format;
+use crate::graph::Graph;
+use crate::index::Idx;
+use crate::vertex::Vertex;
+use crate::weight::{self, Weight};
+use crate::{PropTrait, VtxTrait};
+
+/// A graph that can be exported: its vertices 0..export_v(), their edges,
+/// and optionally a value for each vertex and a weight for each edge
+pub trait Export {
+    fn export_v(&self) -> usize;
+
+    /// Call f with each edge out of vertex v, and its weight if it has one
+    fn export_edges(&self, v: usize, f: &mut dyn FnMut(usize, Option<String>));
+
+    /// The value of vertex v, if it has one
+    fn export_value(&self, v: usize) -> Option<String>;
+}
+
+impl<P: PropTrait + Display, W: Weight, I: Idx, O: Idx> Export for CSR<P, W, I, O> {
+    fn export_v(&self) -> usize {
+        self.get_v()
+    }
+
+    fn export_edges(&self, v: usize, f: &mut dyn FnMut(usize, Option<String>)) {
+        let (st, en) = self.vtx_offset_range(v);
+        for i in st..en {
+            f(self.get_neighbs()[i].index(), self.get_weights().map(|w| w[i].to_string()));
+        }
+    }
+
+    fn export_value(&self, v: usize) -> Option<String> {
+        Some(self.get_vtxprop()[v].to_string())
+    }
+}
+
+impl<I: Idx, O: Idx> Export for FastCSR<I, O> {
+    fn export_v(&self) -> usize {
+        self.getv()
+    }
+
+    fn export_edges(&self, v: usize, f: &mut dyn FnMut(usize, Option<String>)) {
+        match self.weight_kind() {
+            weight::KIND_F64 => weighted_edges::<f64, I, O>(self, v, f),
+            weight::KIND_F32 => weighted_edges::<f32, I, O>(self, v, f),
+            weight::KIND_U64 => weighted_edges::<u64, I, O>(self, v, f),
+            weight::KIND_U32 => weighted_edges::<u32, I, O>(self, v, f),
+            weight::KIND_I64 => weighted_edges::<i64, I, O>(self, v, f),
+            weight::KIND_I32 => weighted_edges::<i32, I, O>(self, v, f),
+            _ => {
+                for n in self.neighbors(v) {
+                    f(n.index(), None);
+                }
+            }
+        }
+    }
+
+    fn export_value(&self, _v: usize) -> Option<String> {
+        None
+    }
+}
+
+/*Call f with each edge out of vertex v of a FastCSR whose weights are Ws*/
+fn weighted_edges<W: Weight, I: Idx, O: Idx>(g: &FastCSR<I, O>, v: usize, f: &mut dyn FnMut(usize, Option<String>)) {
+    let (nbs, wts) = g.weighted_neighbors::<W>(v);
+    for (n, w) in nbs.iter().zip(wts) {
+        f(n.index(), Some(w.to_string()));
+    }
+}
+
+/*A Graph's vertices are numbered by their position, which is also their id*/
+impl<T: VtxTrait> Export for Graph<T> {
+    fn export_v(&self) -> usize {
+        self.num_vtxs()
+    }
+
+    fn export_edges(&self, v: usize, f: &mut dyn FnMut(usize, Option<String>)) {
+        if let Vertex::V { neigh, .. } = self.get_vtx(v) {
+            for n in neigh {
+                f(**n as usize, None);
+            }
+        }
+    }
+
+    fn export_value(&self, v: usize) -> Option<String> {
+        match self.get_vtx(v) {
+            Vertex::V { val, .. } => Some(val.to_string()),
+            Vertex::Empty => None,
+        }
+    }
+}
+
+/// A graph exported with values from a separate slice, one per vertex,
+/// in place of its own; see with_values
+pub struct WithValues<'a, G, T> {
+    g: &'a G,
+    values: &'a [T],
+}
+
+/// Export g with values[v] as the value of each vertex v, e.g., a FastCSR
+/// with the depths a BFS found.  Fails with an InvalidInput i/o error if
+/// there is not one value per vertex.
+pub fn with_values<'a, G: Export, T: Display>(g: &'a G, values: &'a [T]) -> Result<WithValues<'a, G, T>> {
+    if values.len() != g.export_v() {
+        return Err(Error::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} values for {} vertices", values.len(), g.export_v()),
+        )));
+    }
+    Ok(WithValues { g, values })
+}
+
+impl<'a, G: Export, T: Display> Export for WithValues<'a, G, T> {
+    fn export_v(&self) -> usize {
+        self.g.export_v()
+    }
+
+    fn export_edges(&self, v: usize, f: &mut dyn FnMut(usize, Option<String>)) {
+        self.g.export_edges(v, f)
+    }
+
+    fn export_value(&self, v: usize) -> Option<String> {
+        Some(self.values[v].to_string())
+    }
+}
+
+/// What to highlight in an exported graph.  The default highlights nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExportOptions<'a> {
+    /// Vertices to highlight
+    pub vertices: &'a [usize],
+    /// Edges (v0,v1) to highlight, e.g., the (parent,child) edges of a BFS
+    /// tree
+    pub edges: &'a [(usize, usize)],
+}
+
+/*The highlighted vertices and edges, for lookup while writing*/
+struct Marked {
+    vertices: BitVec,
+    edges: HashSet<(usize, usize)>,
+}
+
+impl Marked {
+    fn new(v: usize, opts: &ExportOptions) -> Marked {
+        let mut vertices = BitVec::from_elem(v, false);
+        for u in opts.vertices.iter().filter(|u| **u < v) {
+            vertices.set(*u, true);
+        }
+        Marked {
+            vertices,
+            edges: opts.edges.iter().copied().collect(),
+        }
+    }
+}
+
+/*Quote s as a DOT string*/
+fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/*Escape s for XML text or an attribute value*/
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Write g to w in GraphViz DOT.  Each vertex is labeled with its id and,
+/// if it has one, its value, which is also kept in a value attribute; an
+/// edge's weight is its label.  Highlighted vertices are filled and
+/// highlighted edges drawn thick and red.
+pub fn dot<G: Export + ?Sized>(g: &G, w: &mut dyn Write, opts: &ExportOptions) -> Result<()> {
+    let marked = Marked::new(g.export_v(), opts);
+    writeln!(w, "digraph G {{")?;
+    for v in 0..g.export_v() {
+        let mut attrs = match g.export_value(v) {
+            Some(x) => format!(
+                "label={}, value={}",
+                dot_quote(&format!("{}: {}", v, x)),
+                dot_quote(&x)
+            ),
+            None => format!("label=\"{}\"", v),
+        };
+        if marked.vertices[v] {
+            attrs.push_str(", style=filled, fillcolor=gold");
+        }
+        writeln!(w, "  {} [{}];", v, attrs)?;
+    }
+
+    let mut edges = Vec::new();
+    for v in 0..g.export_v() {
+        g.export_edges(v, &mut |n, wt| edges.push((n, wt)));
+        for (n, wt) in edges.drain(..) {
+            let mut attrs = Vec::new();
+            if let Some(wt) = wt {
+                attrs.push(format!("label={}", dot_quote(&wt)));
+            }
+            if marked.edges.contains(&(v, n)) {
+                attrs.push("color=red, penwidth=2".to_string());
+            }
+            match attrs.is_empty() {
+                true => writeln!(w, "  {} -> {};", v, n)?,
+                false => writeln!(w, "  {} -> {} [{}];", v, n, attrs.join(", "))?,
+            }
+        }
+    }
+    writeln!(w, "}}")?;
+    Ok(())
+}
+
+/// Write g to w in GraphML, with nodes n0, n1, ... carrying value and
+/// highlight data, and edges carrying weight and highlight data
+pub fn graphml<G: Export + ?Sized>(g: &G, w: &mut dyn Write, opts: &ExportOptions) -> Result<()> {
+    let marked = Marked::new(g.export_v(), opts);
+    writeln!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(w, "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">")?;
+    writeln!(w, "  <key id=\"value\" for=\"node\" attr.name=\"value\" attr.type=\"string\"/>")?;
+    writeln!(w, "  <key id=\"vhighlight\" for=\"node\" attr.name=\"highlight\" attr.type=\"boolean\"><default>false</default></key>")?;
+    writeln!(w, "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"string\"/>")?;
+    writeln!(w, "  <key id=\"ehighlight\" for=\"edge\" attr.name=\"highlight\" attr.type=\"boolean\"><default>false</default></key>")?;
+    writeln!(w, "  <graph id=\"G\" edgedefault=\"directed\">")?;
+    for v in 0..g.export_v() {
+        let mut data = String::new();
+        if let Some(x) = g.export_value(v) {
+            data.push_str(&format!("<data key=\"value\">{}</data>", xml_escape(&x)));
+        }
+        if marked.vertices[v] {
+            data.push_str("<data key=\"vhighlight\">true</data>");
+        }
+        writeln!(w, "    <node id=\"n{}\">{}</node>", v, data)?;
+    }
+
+    let mut edges = Vec::new();
+    for v in 0..g.export_v() {
+        g.export_edges(v, &mut |n, wt| edges.push((n, wt)));
+        for (n, wt) in edges.drain(..) {
+            let mut data = String::new();
+            if let Some(wt) = wt {
+                data.push_str(&format!("<data key=\"weight\">{}</data>", xml_escape(&wt)));
+            }
+            if marked.edges.contains(&(v, n)) {
+                data.push_str("<data key=\"ehighlight\">true</data>");
+            }
+            writeln!(w, "    <edge source=\"n{}\" target=\"n{}\">{}</edge>", v, n, data)?;
+        }
+    }
+    writeln!(w, "  </graph>")?;
+    writeln!(w, "</graphml>")?;
+    Ok(())
+}
+
+/// Write g as a DOT file at path; see dot
+pub fn write_dot<G: Export + ?Sized, Q: AsRef<Path>>(g: &G, path: Q, opts: &ExportOptions) -> Result<()> {
+    format::write_file(path, |w| dot(g, w, opts))
+}
+
+/// Write g as a GraphML file at path; see graphml
+pub fn write_graphml<G: Export + ?Sized, Q: AsRef<Path>>(g: &G, path: Q, opts: &ExportOptions) -> Result<()> {
+    format::write_file(path, |w| graphml(g, w, opts))
+}
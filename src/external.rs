@@ -0,0 +1,309 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  External-memory construction of a FastCSR file from a binary edge list
+  (pairs of 8-byte vertex ids) that may be larger than RAM.  The edge list
+  is read three times (four when symmetrizing), never more than chunk_edges
+  edges at a time:
+
+  1. Count each vertex's out-degree.  This is the only O(|V|) state.
+  2. Split the vertices into buckets, contiguous ranges of sources whose
+     edges fit in one chunk, and append each chunk's edges to a run file
+     per bucket in the temporary directory.  When symmetrizing, the edge
+     list is read a second time to append the reverse edges after all of
+     the original ones.
+  3. Load one bucket's run at a time, place its edges into per-vertex
+     order with a counting sort, and append them to the neighbs section of
+     the output.  The offsets and header go in last, once the final degrees
+     are known.
+
+  Runs are appended in input order and the counting sort is stable, so each
+  vertex's neighbors come out in edge list order, followed by the reverse
+  edges in edge list order if symmetrizing, as in a deterministic build.
+*/
+
+use byte_slice_cast::*;
+use rayon::prelude::*;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::csr::BuildOptions;
+use crate::error::{Error, FormatError, Result};
+use crate::format::{self, AtomicFile, Header};
+
+/// Builds a FastCSR file from a binary edge list in bounded memory, using
+/// temporary files for the edges that do not fit.  Memory use is about
+/// |V| words plus a few chunk_edges-sized buffers.
+#[derive(Debug, Clone)]
+pub struct ExternalBuilder {
+    chunk_edges: usize,
+    tmp_dir: PathBuf,
+    numv: Option<usize>,
+    opts: BuildOptions,
+}
+
+impl Default for ExternalBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/*Numbers builds in this process so concurrent builds get distinct runs*/
+static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+/*The run files of one build, removed when the build finishes or fails*/
+struct Runs {
+    paths: Vec<PathBuf>,
+}
+
+impl Drop for Runs {
+    fn drop(&mut self) {
+        for p in &self.paths {
+            let _ = fs::remove_file(p);
+        }
+    }
+}
+
+/*Read a binary edge list chunk_edges edges at a time, handing f each chunk
+as a slice of words, two per edge*/
+fn for_each_chunk(path: &Path, chunk_edges: usize, mut f: impl FnMut(&[usize]) -> Result<()>) -> Result<()> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len() as usize;
+    if !len.is_multiple_of(16) {
+        return Err(FormatError::EdgeListLength(len).into());
+    }
+
+    let mut buf = vec![0usize; 2 * chunk_edges.min(len / 16)];
+    let mut left = len / 8;
+    while left > 0 {
+        let words = left.min(buf.len());
+        file.read_exact(buf[..words].as_mut_byte_slice())?;
+        f(&buf[..words])?;
+        left -= words;
+    }
+    Ok(())
+}
+
+impl ExternalBuilder {
+    /// A builder that reads 16M edges (256MB of edge list) at a time and
+    /// keeps its temporary files in std::env::temp_dir()
+    pub fn new() -> ExternalBuilder {
+        ExternalBuilder {
+            chunk_edges: 1 << 24,
+            tmp_dir: std::env::temp_dir(),
+            numv: None,
+            opts: BuildOptions::default(),
+        }
+    }
+
+    /// Read and hold at most n edges at a time.  A vertex with more than n
+    /// out-edges still gets loaded whole.
+    pub fn chunk_edges(mut self, n: usize) -> ExternalBuilder {
+        self.chunk_edges = n.max(1);
+        self
+    }
+
+    /// Keep the temporary run files in dir
+    pub fn tmp_dir<P: AsRef<Path>>(mut self, dir: P) -> ExternalBuilder {
+        self.tmp_dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Build a graph of v vertices, failing if the edge list names a vertex
+    /// >= v.  Without this, v is one more than the largest id in the file.
+    pub fn numv(mut self, v: usize) -> ExternalBuilder {
+        self.numv = Some(v);
+        self
+    }
+
+    /// Symmetrize, drop self-loops, dedup, or sort as the options say.  The
+    /// output is always in edge list order per vertex, with any reverse
+    /// edges after the original ones, so deterministic has no effect.
+    pub fn options(mut self, opts: BuildOptions) -> ExternalBuilder {
+        self.opts = opts;
+        self
+    }
+
+    /*Hand emit the edge the options turn edge (v0,v1) into, if any, or its
+    reverse if reversed*/
+    fn expand(&self, v0: usize, v1: usize, reversed: bool, mut emit: impl FnMut(usize, usize)) {
+        match (reversed, v0 == v1) {
+            (false, true) if self.opts.remove_self_loops => (),
+            (false, _) => emit(v0, v1),
+            (true, true) => (),
+            (true, false) => emit(v1, v0),
+        }
+    }
+
+    /// Build a FastCSR file at out from the binary edge list at el, and
+    /// return the header written to it.  The file is written under a
+    /// temporary name and renamed to out once it is complete.
+    pub fn build<P: AsRef<Path>, Q: AsRef<Path>>(&self, el: P, out: Q) -> Result<Header> {
+        let el = el.as_ref();
+
+        /*Pass 1: out-degrees*/
+        let mut degs = vec![0usize; self.numv.unwrap_or(0)];
+        for_each_chunk(el, self.chunk_edges, |words| {
+            for e in words.chunks(2) {
+                let maxv = e[0].max(e[1]);
+                match self.numv {
+                    Some(v) if maxv >= v => return Err(Error::VertexOutOfRange { vtx: maxv, v }),
+                    Some(_) => (),
+                    None if maxv >= degs.len() => degs.resize(maxv + 1, 0),
+                    None => (),
+                }
+                self.expand(e[0], e[1], false, |v0, _| degs[v0] += 1);
+                if self.opts.symmetrize {
+                    self.expand(e[0], e[1], true, |v0, _| degs[v0] += 1);
+                }
+            }
+            Ok(())
+        })?;
+        let v = degs.len();
+
+        /*Buckets of contiguous sources, each with at most chunk_edges edges
+        unless a single vertex has more*/
+        let mut starts = vec![0];
+        let mut fill = 0;
+        for (u, d) in degs.iter().enumerate() {
+            if fill > 0 && fill + d > self.chunk_edges {
+                starts.push(u);
+                fill = 0;
+            }
+            fill += d;
+        }
+        let bucket_of = |u: usize| starts.partition_point(|&s| s <= u) - 1;
+
+        /*Pass 2: spill each chunk's edges to its buckets' runs, and then the
+        reverse edges in another read if symmetrizing*/
+        let id = BUILDS.fetch_add(1, Ordering::SeqCst);
+        let runs = Runs {
+            paths: (0..starts.len())
+                .map(|b| {
+                    let name = format!("raphy_ext_{}_{}_{}.run", std::process::id(), id, b);
+                    self.tmp_dir.join(name)
+                })
+                .collect(),
+        };
+        for p in &runs.paths {
+            File::create(p)?;
+        }
+        let mut stage: Vec<Vec<usize>> = vec![Vec::new(); starts.len()];
+        let passes: &[bool] = if self.opts.symmetrize { &[false, true] } else { &[false] };
+        for reversed in passes {
+            for_each_chunk(el, self.chunk_edges, |words| {
+                for e in words.chunks(2) {
+                    self.expand(e[0], e[1], *reversed, |v0, v1| {
+                        stage[bucket_of(v0)].extend_from_slice(&[v0, v1]);
+                    });
+                }
+                for (b, s) in stage.iter_mut().enumerate().filter(|(_, s)| !s.is_empty()) {
+                    let mut run = OpenOptions::new().append(true).open(&runs.paths[b])?;
+                    run.write_all(s.as_byte_slice())?;
+                    s.clear();
+                }
+                Ok(())
+            })?;
+        }
+        drop(stage);
+
+        /*Pass 3: sort each bucket into place and append it to neighbs.  The
+        neighbs section starts right after the offsets no matter what e is.*/
+        let mut flags = 0;
+        if !self.opts.symmetrize {
+            flags |= format::FLAG_DIRECTED;
+        }
+        if self.opts.sort || self.opts.dedup {
+            flags |= format::FLAG_SORTED;
+        }
+        let (mut h, mut e) = (Header::new(v, 0, flags), 0);
+        let out = AtomicFile::new(out);
+        let file = out.write(|w| {
+            w.seek(SeekFrom::Start(h.neighbs_off as u64))?;
+
+            for (b, path) in runs.paths.iter().enumerate() {
+                let lo = starts[b];
+                let hi = starts.get(b + 1).cloned().unwrap_or(v);
+
+                let mut run = vec![0usize; 2 * degs[lo..hi].iter().sum::<usize>()];
+                File::open(path)?.read_exact(run.as_mut_byte_slice())?;
+                fs::remove_file(path)?;
+
+                /*Stable counting sort of the bucket's edges by source*/
+                let mut pos = Vec::with_capacity(hi - lo);
+                let mut acc = 0;
+                for d in &degs[lo..hi] {
+                    pos.push(acc);
+                    acc += d;
+                }
+                let mut nbs = vec![0usize; acc];
+                for e in run.chunks(2) {
+                    nbs[pos[e[0] - lo]] = e[1];
+                    pos[e[0] - lo] += 1;
+                }
+                drop(run);
+
+                if self.opts.sort || self.opts.dedup {
+                    let mut segs = Vec::with_capacity(hi - lo);
+                    let mut rest: &mut [usize] = &mut nbs;
+                    for d in &degs[lo..hi] {
+                        let (seg, r) = std::mem::take(&mut rest).split_at_mut(*d);
+                        segs.push(seg);
+                        rest = r;
+                    }
+                    segs.par_iter_mut().for_each(|seg| seg.sort_unstable());
+                }
+
+                let mut st = 0;
+                for d in degs[lo..hi].iter_mut() {
+                    let seg = &mut nbs[st..st + *d];
+                    st += *d;
+                    let kept = match self.opts.dedup {
+                        true => {
+                            let mut uniq = seg.to_vec();
+                            uniq.dedup();
+                            w.write_all(uniq.as_byte_slice())?;
+                            uniq.len()
+                        }
+                        false => {
+                            w.write_all(seg.as_byte_slice())?;
+                            seg.len()
+                        }
+                    };
+                    *d = kept;
+                    e += kept;
+                }
+            }
+
+            /*Offsets and the header, now that the degrees are final*/
+            h = Header::new(v, e, flags);
+            let mut off = 0;
+            for d in degs.iter_mut() {
+                let k = *d;
+                *d = off;
+                off += k;
+            }
+            w.seek(SeekFrom::Start(0))?;
+            w.write_all(&h.to_bytes())?;
+            w.write_all(degs.as_byte_slice())?;
+            Ok(())
+        })?;
+        file.set_len(h.file_len().ok_or(FormatError::TooLarge { v, e })? as u64)?;
+        out.commit(file)?;
+
+        Ok(h)
+    }
+}
@@ -15,17 +15,202 @@ use byte_slice_cast::*;
 use memmap2::Mmap;
 use rayon::prelude::*;
 use std::fs::OpenOptions;
-use std::path::PathBuf;
+use std::marker::PhantomData;
+use std::path::Path;
 
-pub struct FastCSR {
+use crate::bfs::{self, BfsTree, Direction, DirectionOptions};
+use crate::compressed;
+use crate::dfs::{self, DfsVisitor};
+use crate::error::{Error, FormatError, Result};
+use crate::format::{self, Header};
+use crate::index::Idx;
+use crate::weight::Weight;
+use crate::PropTrait;
+
+/// How much of a FastCSR file to check when loading it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Validation {
+    /// Only check the header and that the file is long enough for it
+    Header,
+    /// Also check offsets and neighbor ids, scanning the file on one thread
+    Sequential,
+    /// Also check offsets and neighbor ids, scanning the file in parallel
+    Parallel,
+}
+
+/// A CSR graph mapped from a FastCSR file.  Neighbor ids are read as I and
+/// offsets as O, which must be the widths the file records (see open_as and
+/// open_any).
+pub struct FastCSR<I = usize, O = usize> {
     v: usize,
     e: usize,
+    flags: u64,
     obase: usize,
+    oend: usize,
     nbase: usize,
+    nend: usize,
+    wbase: usize,
+    wend: usize,
+    wkind: u64,
+    /*In-edge sections, empty ranges if the file has no in-edges*/
+    iobase: usize,
+    ioend: usize,
+    inbase: usize,
+    inend: usize,
+    iwbase: usize,
+    iwend: usize,
     raw: Box<Mmap>,
+    index: PhantomData<(I, O)>,
+}
+
+/// A FastCSR opened without knowing its id and offset widths up front: one
+/// of the four combinations of 4- and 8-byte ids and offsets
+pub enum AnyFastCSR {
+    Wide(FastCSR<usize, usize>),
+    NarrowIds(FastCSR<u32, usize>),
+    NarrowOffsets(FastCSR<usize, u32>),
+    Narrow(FastCSR<u32, u32>),
+}
+
+/*Evaluate the same expression on whichever FastCSR an AnyFastCSR holds*/
+macro_rules! any {
+    ($g:expr, $f:ident => $e:expr) => {
+        match $g {
+            AnyFastCSR::Wide($f) => $e,
+            AnyFastCSR::NarrowIds($f) => $e,
+            AnyFastCSR::NarrowOffsets($f) => $e,
+            AnyFastCSR::Narrow($f) => $e,
+        }
+    };
+}
+
+impl AnyFastCSR {
+    pub fn getv(&self) -> usize {
+        any!(self, g => g.getv())
+    }
+
+    pub fn gete(&self) -> usize {
+        any!(self, g => g.gete())
+    }
+
+    /// Width in bytes of a neighbor id in the file
+    pub fn id_width(&self) -> usize {
+        match self {
+            AnyFastCSR::NarrowIds(_) | AnyFastCSR::Narrow(_) => 4,
+            _ => std::mem::size_of::<usize>(),
+        }
+    }
+
+    /// Width in bytes of an offset in the file
+    pub fn offset_width(&self) -> usize {
+        match self {
+            AnyFastCSR::NarrowOffsets(_) | AnyFastCSR::Narrow(_) => 4,
+            _ => std::mem::size_of::<usize>(),
+        }
+    }
+
+    /// The number of edges out of vertex i
+    pub fn out_degree(&self, i: usize) -> usize {
+        any!(self, g => g.out_degree(i))
+    }
+
+    /// Apply f to every edge (v0,v1) in parallel, whatever the widths
+    pub fn read_only_scan(&self, f: impl Fn(usize, usize) + std::marker::Sync) {
+        any!(self, g => g.read_only_scan(f))
+    }
+
+    /// Parallel breadth first search from vertex start, whatever the widths
+    pub fn par_bfs(&self, start: usize) -> BfsTree {
+        any!(self, g => g.par_bfs(start))
+    }
+
+    /// Direction-optimizing parallel breadth first search from vertex
+    /// start, whatever the widths
+    pub fn par_do_bfs(&self, start: usize, opts: &DirectionOptions) -> (BfsTree, Vec<Direction>) {
+        any!(self, g => g.par_do_bfs(start, opts))
+    }
+
+    /// Depth first search from vertex start, whatever the widths
+    pub fn dfs(&self, start: usize, vis: &mut impl DfsVisitor) {
+        any!(self, g => g.dfs(start, vis))
+    }
+
+    /// Depth first search of the whole graph, whatever the widths
+    pub fn dfs_all(&self, vis: &mut impl DfsVisitor) {
+        any!(self, g => g.dfs_all(vis))
+    }
+}
+
+pub(crate) fn map(path: &Path) -> Result<Box<Mmap>> {
+    let file = OpenOptions::new().read(true).open(path)?;
+
+    Ok(Box::new(unsafe { Mmap::map(&file)? }))
 }
 
 impl FastCSR {
+    /// Load a FastCSR from a file in the version 2 format written by
+    /// CSR::write_fastcsr, checking the whole file for consistency (in parallel)
+    /// before returning it.  The file must hold usize ids and offsets; see
+    /// open_as and open_any for narrower ones.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<FastCSR> {
+        FastCSR::open_with(path, Validation::Parallel)
+    }
+
+    /// Load a FastCSR from a file in the version 2 format, checking as much
+    /// of the file as the validation level asks for.  Files in the legacy
+    /// headerless format are rejected; load those with open_legacy.
+    pub fn open_with<P: AsRef<Path>>(path: P, check: Validation) -> Result<FastCSR> {
+        FastCSR::open_as(path, check)
+    }
+
+    /// Load a FastCSR from a file in the version 2 format with whichever id
+    /// and offset widths its header records
+    pub fn open_any<P: AsRef<Path>>(path: P, check: Validation) -> Result<AnyFastCSR> {
+        let mmap = map(path.as_ref())?;
+        let h = Header::parse(&mmap)?;
+
+        Ok(match (h.id_width(), h.offset_width()) {
+            (4, 4) => AnyFastCSR::Narrow(FastCSR::from_parts(mmap, h, check)?),
+            (4, _) => AnyFastCSR::NarrowIds(FastCSR::from_parts(mmap, h, check)?),
+            (_, 4) => AnyFastCSR::NarrowOffsets(FastCSR::from_parts(mmap, h, check)?),
+            _ => AnyFastCSR::Wide(FastCSR::from_parts(mmap, h, check)?),
+        })
+    }
+
+    /// Load a FastCSR from a file in the legacy headerless format:
+    /// |v|e|offsets|neighbs|
+    pub fn open_legacy<P: AsRef<Path>>(path: P, check: Validation) -> Result<FastCSR> {
+        let mmap = map(path.as_ref())?;
+        if mmap.len() < 16 {
+            return Err(Error::Format(FormatError::Truncated {
+                len: mmap.len(),
+                need: 16,
+            }));
+        }
+
+        let csr = mmap[..16].as_slice_of::<usize>().unwrap();
+        let (v, e) = (csr[0], csr[1]);
+        match Header::legacy(v, e) {
+            Some(h) => FastCSR::from_parts(mmap, h, check),
+            None => Err(Error::Format(FormatError::TooLarge { v, e })),
+        }
+    }
+
+    /// Load a FastCSR from a file in the version 2 format, checking only the
+    /// header.  Files in the legacy headerless format fail with BadMagic; load
+    /// those with new_legacy.
+    pub fn new(s: String) -> Result<FastCSR> {
+        FastCSR::open_with(s, Validation::Header)
+    }
+
+    /// Load a FastCSR from a file in the legacy headerless format, checking
+    /// only that the file is long enough for the counts at its start
+    pub fn new_legacy(s: String) -> Result<FastCSR> {
+        FastCSR::open_legacy(s, Validation::Header)
+    }
+} /*impl FastCSR*/
+
+impl<I: Idx, O: Idx> FastCSR<I, O> {
     pub fn getv(&self) -> usize {
         self.v
     }
@@ -34,42 +219,166 @@ impl FastCSR {
         self.e
     }
 
-    pub fn new(s: String) -> FastCSR {
-        let path = PathBuf::from(s);
-        let file = OpenOptions::new().read(true).open(&path).unwrap();
+    pub fn is_weighted(&self) -> bool {
+        self.flags & format::FLAG_WEIGHTED != 0
+    }
+
+    /// The weight::KIND_* tag of the weights section, 0 if unweighted
+    pub fn weight_kind(&self) -> u64 {
+        self.wkind
+    }
+
+    pub fn is_sorted(&self) -> bool {
+        self.flags & format::FLAG_SORTED != 0
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.flags & format::FLAG_DIRECTED != 0
+    }
+
+    /// True if the file also stores each vertex's in-neighbors
+    pub fn has_in_edges(&self) -> bool {
+        self.flags & format::FLAG_IN_EDGES != 0
+    }
+
+    /// Load a FastCSR from a file in the version 2 format whose ids are
+    /// stored as I and offsets as O, e.g., FastCSR::<u32, u32>::open_as for
+    /// a file written from a CompactCSR.  Fails with IndexWidth if the file
+    /// records other widths.
+    pub fn open_as<P: AsRef<Path>>(path: P, check: Validation) -> Result<FastCSR<I, O>> {
+        let mmap = map(path.as_ref())?;
+        let h = Header::parse(&mmap)?;
 
-        let mmap = Box::new(unsafe { Mmap::map(&file).unwrap() });
+        FastCSR::from_parts(mmap, h, check)
+    }
 
-        assert!(mmap.len() >= 8);
-        let csr = mmap[..]
-                  .as_slice_of::<usize>()
-                  .unwrap();
+    fn from_parts(raw: Box<Mmap>, h: Header, check: Validation) -> Result<FastCSR<I, O>> {
+        let (id, offset) = (h.id_width(), h.offset_width());
+        if id != I::WIDTH || offset != O::WIDTH {
+            return Err(Error::Format(FormatError::IndexWidth { id, offset }));
+        }
+        if h.is_compressed() {
+            return Err(Error::Format(FormatError::Compressed(true)));
+        }
+
+        let (v, e) = (h.v, h.e);
+        let need = match h.file_len() {
+            Some(need) => need,
+            None => return Err(Error::Format(FormatError::TooLarge { v, e })),
+        };
+        if raw.len() < need {
+            return Err(Error::Format(FormatError::Truncated {
+                len: raw.len(),
+                need,
+            }));
+        }
 
-        let v = csr[0];
-        let e = csr[1];
+        let g = FastCSR {
+            v,
+            e,
+            flags: h.flags,
+            obase: h.offsets_off,
+            oend: h.offsets_off + v * O::WIDTH,
+            nbase: h.neighbs_off,
+            nend: h.neighbs_off + e * I::WIDTH,
+            wbase: h.weights_off,
+            wend: h.weights_off + e * h.weight_width(),
+            wkind: h.weight_kind(),
+            iobase: h.in_offsets_off,
+            ioend: h.in_offsets_off + if h.has_in_edges() { v * O::WIDTH } else { 0 },
+            inbase: h.in_neighbs_off,
+            inend: h.in_neighbs_off + if h.has_in_edges() { e * I::WIDTH } else { 0 },
+            iwbase: h.in_weights_off,
+            iwend: h.in_weights_off + if h.in_weights_off != 0 { e * h.weight_width() } else { 0 },
+            raw,
+            index: PhantomData,
+        };
 
-        println!("{} edges total", e);
-        FastCSR {
-            v: v,
-            e: e,
-            obase: 16,
-            nbase: 16 + v * 8,
-            raw: mmap,
+        match check {
+            Validation::Header => (),
+            Validation::Sequential => g.validate(false)?,
+            Validation::Parallel => g.validate(true)?,
         }
+
+        Ok(g)
     }
 
-    pub fn offset(&self, i: usize) -> usize {
-        let offsets = &self.raw[self.obase..self.nbase]
-            .as_slice_of::<usize>()
-            .unwrap();
+    /// Check that the offsets are non-decreasing and no larger than e, and that
+    /// every neighbor id is less than v, for the out-edges and, if the file
+    /// has them, the in-edges.  Reports the first problem found.
+    pub fn validate(&self, parallel: bool) -> Result<()> {
+        self.validate_sections(self.offsets(), self.edges(), parallel)?;
+        if self.has_in_edges() {
+            self.validate_sections(self.in_offsets(), self.in_edges(), parallel)?;
+        }
+        Ok(())
+    }
 
-        offsets[i]
+    fn validate_sections(&self, offsets: &[O], neighbs: &[I], parallel: bool) -> Result<()> {
+        let bad_offset = |i: &usize| match *i {
+            0 => offsets[0].index() != 0,
+            i => offsets[i] < offsets[i - 1],
+        };
+        let bad = match parallel {
+            true => (0..self.v).into_par_iter().find_first(bad_offset),
+            false => (0..self.v).find(bad_offset),
+        };
+        match bad {
+            Some(0) => return Err(Error::Format(FormatError::FirstOffsetNonZero(offsets[0].index()))),
+            Some(vtx) => return Err(Error::Format(FormatError::OffsetsNotMonotonic { vtx })),
+            None => (),
+        }
+
+        /*Offsets are monotonic, so only the last one can be too large*/
+        if self.v > 0 && offsets[self.v - 1].index() > self.e {
+            return Err(Error::Format(FormatError::OffsetOutOfRange {
+                vtx: self.v - 1,
+                offset: offsets[self.v - 1].index(),
+                e: self.e,
+            }));
+        }
+
+        let bad_neighbor = |i: &usize| neighbs[*i].index() >= self.v;
+        let bad = match parallel {
+            true => (0..self.e).into_par_iter().find_first(bad_neighbor),
+            false => (0..self.e).find(bad_neighbor),
+        };
+        if let Some(i) = bad {
+            /*The vertex owning neighbs[i] is the last one whose offset is <= i*/
+            let vtx = offsets.partition_point(|o| o.index() <= i) - 1;
+            return Err(Error::Format(FormatError::NeighborOutOfRange {
+                vtx,
+                neighbor: neighbs[i].index(),
+                v: self.v,
+            }));
+        }
+
+        Ok(())
+    }
+
+    fn offsets(&self) -> &[O] {
+        self.raw[self.obase..self.oend].as_slice_of::<O>().unwrap()
+    }
+
+    fn edges(&self) -> &[I] {
+        self.raw[self.nbase..self.nend].as_slice_of::<I>().unwrap()
+    }
+
+    fn in_offsets(&self) -> &[O] {
+        self.raw[self.iobase..self.ioend].as_slice_of::<O>().unwrap()
+    }
+
+    fn in_edges(&self) -> &[I] {
+        self.raw[self.inbase..self.inend].as_slice_of::<I>().unwrap()
     }
 
-    pub fn neighbors(&self, i: usize) -> &[usize] {
+    pub fn offset(&self, i: usize) -> usize {
+        self.offsets()[i].index()
+    }
+
+    pub fn neighbors(&self, i: usize) -> &[I] {
         let (n0, nn) = self.vtx_offset_range(i);
-        let edges = &self.raw[self.nbase..].as_slice_of::<usize>().unwrap();
-        &edges[n0..nn]
+        &self.edges()[n0..nn]
     }
 
     fn vtx_offset_range(&self, v: usize) -> (usize, usize) {
@@ -81,30 +390,187 @@ impl FastCSR {
             },
         )
     }
-    pub fn neighbor_scan_prop(&self, f: impl Fn(usize, &[usize]) -> f64 + std::marker::Sync, prop: &mut [f64]) {
+
+    /// The number of edges out of vertex i
+    pub fn out_degree(&self, i: usize) -> usize {
+        let (n0, nn) = self.vtx_offset_range(i);
+        nn - n0
+    }
+
+    fn check_in_edges(&self) {
+        if !self.has_in_edges() {
+            panic!("FastCSR file has no in-edges");
+        }
+    }
+
+    fn in_offset_range(&self, v: usize) -> (usize, usize) {
+        self.check_in_edges();
+        let offsets = self.in_offsets();
+        (
+            offsets[v].index(),
+            match v {
+                v if v == self.v - 1 => self.e,
+                _ => offsets[v + 1].index(),
+            },
+        )
+    }
+
+    /// The vertices with an edge to vertex i.  Panics if the file has no
+    /// in-edges (see has_in_edges).
+    pub fn in_neighbors(&self, i: usize) -> &[I] {
+        let (n0, nn) = self.in_offset_range(i);
+        &self.in_edges()[n0..nn]
+    }
+
+    /// The number of edges into vertex i.  Panics if the file has no in-edges.
+    pub fn in_degree(&self, i: usize) -> usize {
+        let (n0, nn) = self.in_offset_range(i);
+        nn - n0
+    }
+
+    /// The vertices with an edge to vertex i and the weights of those edges.
+    /// Panics if the file has no in-edges or no weights of type W.
+    pub fn weighted_in_neighbors<W: Weight>(&self, i: usize) -> (&[I], &[W]) {
+        let (n0, nn) = self.in_offset_range(i);
+        if let Err(e) = self.check_weights::<W>() {
+            panic!("{}", e);
+        }
+        let weights = self.raw[self.iwbase..self.iwend].as_slice_of::<W>().unwrap();
+        (&self.in_edges()[n0..nn], &weights[n0..nn])
+    }
+
+    /// Like neighbor_scan_prop, but f gets each vertex's in-neighbors, for
+    /// pull-style computations.  Panics if the file has no in-edges.
+    pub fn in_neighbor_scan_prop<P: PropTrait>(&self, f: impl Fn(usize, &[I]) -> P + std::marker::Sync, prop: &mut [P]) {
+        self.check_in_edges();
+        prop.par_iter_mut().enumerate().for_each(|(v, p)| {
+            *p = f(v, self.in_neighbors(v));
+        });
+    }
+    pub fn neighbor_scan_prop<P: PropTrait>(&self, f: impl Fn(usize, &[I]) -> P + std::marker::Sync, prop: &mut [P]) {
         prop.par_iter_mut().enumerate().for_each(|(v,p)| {
             let (n0, nn) = self.vtx_offset_range(v);
-            let edges = &self.raw[self.nbase..].as_slice_of::<usize>().unwrap();
+            let edges = self.edges();
             let res = f(v, &edges[n0..nn]);
             *p = res;
         });
     }
 
-    pub fn neighbor_scan(&self, f: impl Fn(usize, &[usize]) -> () + std::marker::Sync) {
+    pub fn neighbor_scan(&self, f: impl Fn(usize, &[I]) + std::marker::Sync) {
         (0..self.v).into_par_iter().for_each(|v| {
             let (n0, nn) = self.vtx_offset_range(v);
-            let edges = &self.raw[self.nbase..].as_slice_of::<usize>().unwrap();
+            let edges = self.edges();
             f(v, &edges[n0..nn]);
         });
     }
 
-    pub fn read_only_scan(&self, f: impl Fn(usize, usize) -> () + std::marker::Sync) {
+    pub fn read_only_scan(&self, f: impl Fn(usize, usize) + std::marker::Sync) {
         (0..self.v).into_par_iter().for_each(|v| {
             let (n0, nn) = self.vtx_offset_range(v);
-            let edges = self.raw[self.nbase..].as_slice_of::<usize>().unwrap();
-            edges[n0..nn].into_iter().for_each(|n| {
-                f(v, *n);
+            let edges = self.edges();
+            edges[n0..nn].iter().for_each(|n| {
+                f(v, n.index());
             });
         });
     }
+
+    /// Parallel breadth first search from vertex start, returning each
+    /// vertex's depth and parent (see bfs.rs).  Panics if start is not a
+    /// vertex.
+    pub fn par_bfs(&self, start: usize) -> BfsTree {
+        bfs::bfs(self, start)
+    }
+
+    /// Direction-optimizing parallel breadth first search from vertex
+    /// start, returning the search tree and the direction each level was
+    /// expanded in (see bfs.rs).  Panics if start is not a vertex or the
+    /// graph does not keep its in-edges.
+    pub fn par_do_bfs(&self, start: usize, opts: &DirectionOptions) -> (BfsTree, Vec<Direction>) {
+        bfs::direction_optimizing_bfs(self, start, opts)
+    }
+
+    /// Depth first search from vertex start, calling vis on each vertex
+    /// and edge as it goes (see dfs.rs).  Panics if start is not a vertex.
+    pub fn dfs(&self, start: usize, vis: &mut impl DfsVisitor) {
+        dfs::dfs(self, start, vis)
+    }
+
+    /// Depth first search of the whole graph, starting a new tree at each
+    /// vertex, in id order, not yet reached
+    pub fn dfs_all(&self, vis: &mut impl DfsVisitor) {
+        dfs::dfs_all(self, vis)
+    }
+
+    /// Check that the graph has a weights section holding weights of type W
+    pub fn check_weights<W: Weight>(&self) -> Result<()> {
+        match self.wkind {
+            0 => Err(Error::Format(FormatError::MissingSection("weights"))),
+            k if k != W::KIND => Err(Error::Format(FormatError::WeightKind {
+                expected: W::KIND,
+                found: k,
+            })),
+            _ => Ok(()),
+        }
+    }
+
+    /*The whole weights section.  Panics if it is missing or is not of type W*/
+    fn weights<W: Weight>(&self) -> &[W] {
+        if let Err(e) = self.check_weights::<W>() {
+            panic!("{}", e);
+        }
+        self.raw[self.wbase..self.wend].as_slice_of::<W>().unwrap()
+    }
+
+    /// The neighbors of vertex i and the weights of the edges to them.
+    /// Panics if the graph has no weights of type W (see check_weights).
+    pub fn weighted_neighbors<W: Weight>(&self, i: usize) -> (&[I], &[W]) {
+        let (n0, nn) = self.vtx_offset_range(i);
+        let edges = self.edges();
+        (&edges[n0..nn], &self.weights::<W>()[n0..nn])
+    }
+
+    /// Like neighbor_scan_prop, but f also gets the weights of the edges to
+    /// each vertex's neighbors
+    pub fn neighbor_scan_prop_weighted<P: PropTrait, W: Weight>(
+        &self,
+        f: impl Fn(usize, &[I], &[W]) -> P + std::marker::Sync,
+        prop: &mut [P],
+    ) {
+        let weights = self.weights::<W>();
+        prop.par_iter_mut().enumerate().for_each(|(v, p)| {
+            let (n0, nn) = self.vtx_offset_range(v);
+            let edges = self.edges();
+            *p = f(v, &edges[n0..nn], &weights[n0..nn]);
+        });
+    }
+
+    /// Like neighbor_scan, but f also gets the weights of the edges to
+    /// each vertex's neighbors
+    pub fn neighbor_scan_weighted<W: Weight>(
+        &self,
+        f: impl Fn(usize, &[I], &[W]) + std::marker::Sync,
+    ) {
+        let weights = self.weights::<W>();
+        (0..self.v).into_par_iter().for_each(|v| {
+            let (n0, nn) = self.vtx_offset_range(v);
+            let edges = self.edges();
+            f(v, &edges[n0..nn], &weights[n0..nn]);
+        });
+    }
+
+    /// Write the graph out as a compressed FastCSR file at path, with each
+    /// vertex's neighbors sorted and gap encoded (see compressed.rs).
+    /// Weights and in-edges are not written.
+    pub fn write_compressed<Q: AsRef<Path>>(&self, path: Q) -> Result<Header> {
+        compressed::write(path, self.v, self.is_directed(), |u, buf| {
+            buf.extend(self.neighbors(u).iter().map(|n| n.index()));
+        })
+    }
+
+    /// Like read_only_scan, but f also gets each edge's weight
+    pub fn read_only_scan_weighted<W: Weight>(&self, f: impl Fn(usize, usize, W) + std::marker::Sync) {
+        self.neighbor_scan_weighted(|v, nbs: &[I], wts: &[W]| {
+            nbs.iter().zip(wts).for_each(|(n, w)| f(v, n.index(), *w));
+        });
+    }
 } /*impl FastCSR*/
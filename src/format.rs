@@ -0,0 +1,553 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::{Error, FormatError, Result as RaphyResult};
+use crate::weight::{self, Weight};
+
+/* FastCSR file format, version 2
+  The file starts with a fixed-size header of HEADER_WORDS 8-byte words,
+  stored in the byte order of the machine that wrote the file:
+
+  |magic|version|flags|v|e|offsets_off|neighbs_off|weights_off|
+  |in_offsets_off|in_neighbs_off|in_weights_off|neighbs_len|reserved...|
+
+  followed by the sections that the header points at, each one 8-byte aligned:
+
+  |offsets (v offsets)|neighbs (e ids)|weights (e weights, if weighted)|
+
+  and, if the file also stores the transpose of the graph (FLAG_IN_EDGES),
+  the same three sections again for the in-edges of each vertex:
+
+  |in_offsets (v offsets)|in_neighbs (e ids)|in_weights (e weights, if weighted)|
+
+  Ids and offsets are each 4 or 8 bytes wide, as recorded in the flags, so
+  a graph with fewer than 2^32 vertices or edges can store them as u32.
+  Weights are stored in whatever type the graph was built with; the type is
+  recorded in the flags as one of the weight::KIND_* tags.
+
+  A compressed file (FLAG_COMPRESSED, see compressed.rs) has only the offsets
+  and neighbs sections.  Its offsets are byte offsets into neighbs, which holds
+  each vertex's neighbors as a varint-encoded run of neighbs_len bytes in all.
+  With FLAG_REFERENCES as well, the runs are those of a BvGraph (see bvgraph.rs).
+
+  The legacy (version 1) layout has no header at all, just |v|e|offsets|neighbs|.
+*/
+
+pub const MAGIC: [u8; 8] = *b"RAPHYCSR";
+pub const VERSION: u64 = 2;
+pub const HEADER_WORDS: usize = 16;
+pub const HEADER_BYTES: usize = HEADER_WORDS * 8;
+
+/// Each neighbor list carries a weights section in parallel with neighbs
+pub const FLAG_WEIGHTED: u64 = 1;
+/// Each vertex's neighbor list is sorted by increasing id
+pub const FLAG_SORTED: u64 = 1 << 1;
+/// Edges are directed (u,v) pairs rather than symmetric pairs
+pub const FLAG_DIRECTED: u64 = 1 << 2;
+/// The file was written by a big endian machine
+pub const FLAG_BIG_ENDIAN: u64 = 1 << 3;
+/// The file also stores each vertex's in-neighbors
+pub const FLAG_IN_EDGES: u64 = 1 << 4;
+/// The neighbs section holds varint-encoded neighbor lists (see compressed.rs)
+pub const FLAG_COMPRESSED: u64 = 1 << 5;
+/// The compressed neighbor lists may be coded by reference to earlier ones
+/// (see bvgraph.rs)
+pub const FLAG_REFERENCES: u64 = 1 << 6;
+
+/*Bits 8..16 of flags hold the width in bytes of a neighbor id,
+bits 16..24 hold the width in bytes of an offset,
+bits 24..32 hold the kind of weight, if the graph is weighted*/
+const ID_WIDTH_SHIFT: u64 = 8;
+const OFFSET_WIDTH_SHIFT: u64 = 16;
+const WEIGHT_KIND_SHIFT: u64 = 24;
+const WIDTH_MASK: u64 = 0xff;
+
+const MAGIC_WORD: usize = 0;
+const VERSION_WORD: usize = 1;
+const FLAGS_WORD: usize = 2;
+const V_WORD: usize = 3;
+const E_WORD: usize = 4;
+const OFFSETS_WORD: usize = 5;
+const NEIGHBS_WORD: usize = 6;
+const WEIGHTS_WORD: usize = 7;
+const IN_OFFSETS_WORD: usize = 8;
+const IN_NEIGHBS_WORD: usize = 9;
+const IN_WEIGHTS_WORD: usize = 10;
+const NEIGHBS_LEN_WORD: usize = 11;
+
+/*Round a byte offset up to the next 8-byte boundary*/
+fn align8(off: usize) -> usize {
+    (off + 7) & !7
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub version: u64,
+    pub flags: u64,
+    pub v: usize,
+    pub e: usize,
+    /// Byte offset of the offsets section from the start of the file
+    pub offsets_off: usize,
+    /// Byte offset of the neighbs section from the start of the file
+    pub neighbs_off: usize,
+    /// Byte offset of the weights section, 0 if the graph is unweighted
+    pub weights_off: usize,
+    /// Byte offset of the in-edge offsets section, 0 if there are no in-edges
+    pub in_offsets_off: usize,
+    /// Byte offset of the in-edge neighbs section, 0 if there are no in-edges
+    pub in_neighbs_off: usize,
+    /// Byte offset of the in-edge weights section, 0 if there are no
+    /// in-edges or the graph is unweighted
+    pub in_weights_off: usize,
+    /// Length in bytes of the neighbs section of a compressed file, 0 if
+    /// the file is not compressed
+    pub neighbs_len: usize,
+}
+
+impl Header {
+    /// Lay out a header and its sections for an unweighted graph with v
+    /// vertices and e edges.  The id and offset widths are set to the width
+    /// of a usize; use with_widths to store them narrower.
+    pub fn new(v: usize, e: usize, flags: u64) -> Header {
+        Header::layout(v, e, flags & !FLAG_WEIGHTED)
+    }
+
+    /// Lay out a header and its sections for a graph with v vertices and
+    /// e edges, each with a weight of type W
+    pub fn new_weighted<W: Weight>(v: usize, e: usize, flags: u64) -> Header {
+        Header::layout(v, e, flags | FLAG_WEIGHTED | (W::KIND << WEIGHT_KIND_SHIFT))
+    }
+
+    /// The header of a file in the legacy headerless format,
+    /// |v|e|offsets|neighbs|: version 1, usize ids and offsets, and the
+    /// sections right after the two counts.  None if v is too large for the
+    /// neighbs section to have an offset.
+    pub fn legacy(v: usize, e: usize) -> Option<Header> {
+        let w = std::mem::size_of::<usize>() as u64;
+        Some(Header {
+            version: 1,
+            flags: FLAG_DIRECTED | (w << ID_WIDTH_SHIFT) | (w << OFFSET_WIDTH_SHIFT),
+            v,
+            e,
+            offsets_off: 16,
+            neighbs_off: v.checked_mul(w as usize)?.checked_add(16)?,
+            weights_off: 0,
+            in_offsets_off: 0,
+            in_neighbs_off: 0,
+            in_weights_off: 0,
+            neighbs_len: 0,
+        })
+    }
+
+    fn layout(v: usize, e: usize, flags: u64) -> Header {
+        let w = std::mem::size_of::<usize>() as u64;
+        let mut flags = flags | (w << ID_WIDTH_SHIFT) | (w << OFFSET_WIDTH_SHIFT);
+        if cfg!(target_endian = "big") {
+            flags |= FLAG_BIG_ENDIAN;
+        }
+
+        let mut h = Header {
+            version: VERSION,
+            flags,
+            v,
+            e,
+            offsets_off: 0,
+            neighbs_off: 0,
+            weights_off: 0,
+            in_offsets_off: 0,
+            in_neighbs_off: 0,
+            in_weights_off: 0,
+            neighbs_len: 0,
+        };
+        h.place_sections();
+        h
+    }
+
+    /// Lay out a header and its sections for a compressed graph with v
+    /// vertices and e edges, whose encoded neighbor lists take neighbs_len
+    /// bytes.  Compressed files have no weights or in-edges.
+    pub fn new_compressed(v: usize, e: usize, neighbs_len: usize, flags: u64) -> Header {
+        let mut h = Header::new(v, e, (flags | FLAG_COMPRESSED) & !FLAG_IN_EDGES);
+        h.neighbs_len = neighbs_len;
+        h
+    }
+
+    /*Work out where each section starts from the counts, the widths and
+    which sections the flags say there are*/
+    fn place_sections(&mut self) {
+        let (iw, ow, ww) = (self.id_width(), self.offset_width(), self.weight_width());
+        let weighted = self.is_weighted();
+        let nlen = match self.is_compressed() {
+            true => self.neighbs_len,
+            false => self.e * iw,
+        };
+
+        self.offsets_off = HEADER_BYTES;
+        self.neighbs_off = align8(self.offsets_off + self.v * ow);
+        self.weights_off = match weighted {
+            true => align8(self.neighbs_off + nlen),
+            false => 0,
+        };
+        let out_end = match weighted {
+            true => align8(self.weights_off + self.e * ww),
+            false => align8(self.neighbs_off + nlen),
+        };
+
+        if self.has_in_edges() {
+            self.in_offsets_off = out_end;
+            self.in_neighbs_off = align8(self.in_offsets_off + self.v * ow);
+            self.in_weights_off = match weighted {
+                true => align8(self.in_neighbs_off + self.e * iw),
+                false => 0,
+            };
+        }
+    }
+
+    /// Store neighbor ids in id bytes and offsets in offset bytes (4 or 8)
+    /// instead of a usize each, and lay the sections out again to match
+    pub fn with_widths(mut self, id: usize, offset: usize) -> Header {
+        let mask = (WIDTH_MASK << ID_WIDTH_SHIFT) | (WIDTH_MASK << OFFSET_WIDTH_SHIFT);
+        self.flags = (self.flags & !mask)
+            | ((id as u64) << ID_WIDTH_SHIFT)
+            | ((offset as u64) << OFFSET_WIDTH_SHIFT);
+        self.place_sections();
+        self
+    }
+
+    /// Add in-edge sections for the transpose of the graph after the
+    /// out-edge sections
+    pub fn with_in_edges(mut self) -> Header {
+        self.flags |= FLAG_IN_EDGES;
+        self.place_sections();
+        self
+    }
+
+    /// True if the bytes start with the version 2 magic number
+    pub fn has_magic(bytes: &[u8]) -> bool {
+        bytes.len() >= MAGIC.len() && bytes[..MAGIC.len()] == MAGIC
+    }
+
+    /// Parse a header from the start of a mapped file, failing if the bytes
+    /// do not describe a version 2 header this machine can read.
+    pub fn parse(bytes: &[u8]) -> Result<Header, FormatError> {
+        if bytes.len() < HEADER_BYTES {
+            return Err(FormatError::Truncated {
+                len: bytes.len(),
+                need: HEADER_BYTES,
+            });
+        }
+        if !Header::has_magic(bytes) {
+            return Err(FormatError::BadMagic);
+        }
+
+        let word = |i: usize| {
+            let mut w = [0u8; 8];
+            w.copy_from_slice(&bytes[i * 8..(i + 1) * 8]);
+            u64::from_ne_bytes(w)
+        };
+
+        let version = word(VERSION_WORD);
+        if version != VERSION && version.swap_bytes() == VERSION {
+            return Err(FormatError::ByteOrder);
+        }
+        if version != VERSION {
+            return Err(FormatError::UnsupportedVersion(version));
+        }
+
+        let h = Header {
+            version,
+            flags: word(FLAGS_WORD),
+            v: word(V_WORD) as usize,
+            e: word(E_WORD) as usize,
+            offsets_off: word(OFFSETS_WORD) as usize,
+            neighbs_off: word(NEIGHBS_WORD) as usize,
+            weights_off: word(WEIGHTS_WORD) as usize,
+            in_offsets_off: word(IN_OFFSETS_WORD) as usize,
+            in_neighbs_off: word(IN_NEIGHBS_WORD) as usize,
+            in_weights_off: word(IN_WEIGHTS_WORD) as usize,
+            neighbs_len: word(NEIGHBS_LEN_WORD) as usize,
+        };
+
+        /*Ids and offsets are 4 or 8 bytes, and no wider than a usize here*/
+        let readable = |w: usize| (w == 4 || w == 8) && w <= std::mem::size_of::<usize>();
+        if !readable(h.id_width()) || !readable(h.offset_width()) {
+            return Err(FormatError::IndexWidth {
+                id: h.id_width(),
+                offset: h.offset_width(),
+            });
+        }
+
+        if h.is_weighted() {
+            if weight::kind_width(h.weight_kind()).is_none() {
+                return Err(FormatError::UnknownWeightKind(h.weight_kind()));
+            }
+            if h.weights_off == 0 {
+                return Err(FormatError::MissingSection("weights"));
+            }
+        }
+        if h.has_in_edges() {
+            if h.in_offsets_off == 0 || h.in_neighbs_off == 0 {
+                return Err(FormatError::MissingSection("in-edges"));
+            }
+            if h.is_weighted() && h.in_weights_off == 0 {
+                return Err(FormatError::MissingSection("in-edge weights"));
+            }
+        }
+
+        for &(section, off) in [
+            ("offsets", h.offsets_off),
+            ("neighbs", h.neighbs_off),
+            ("weights", h.weights_off),
+            ("in-edge offsets", h.in_offsets_off),
+            ("in-edge neighbs", h.in_neighbs_off),
+            ("in-edge weights", h.in_weights_off),
+        ]
+        .iter()
+        {
+            if off % 8 != 0 {
+                return Err(FormatError::Misaligned { section, off });
+            }
+        }
+
+        /*Every section lies after the header, and no two share a byte*/
+        let mut sections = h.sections().ok_or(FormatError::TooLarge { v: h.v, e: h.e })?;
+        if let Some(&(section, off, _)) = sections.iter().find(|s| s.1 < HEADER_BYTES) {
+            return Err(FormatError::SectionInHeader { section, off });
+        }
+        sections.retain(|s| s.2 > s.1);
+        sections.sort_unstable_by_key(|s| s.1);
+        if let Some(w) = sections.windows(2).find(|w| w[0].2 > w[1].1) {
+            return Err(FormatError::SectionsOverlap {
+                first: w[0].0,
+                second: w[1].0,
+            });
+        }
+
+        Ok(h)
+    }
+
+    /*The name, start and end in bytes of each section the flags say the
+    file has, or None if an end overflows*/
+    fn sections(&self) -> Option<Vec<(&'static str, usize, usize)>> {
+        let (iw, ow, ww) = (self.id_width(), self.offset_width(), self.weight_width());
+        let section = |name, off: usize, n: usize, w: usize| {
+            Some((name, off, n.checked_mul(w)?.checked_add(off)?))
+        };
+        let mut s = vec![section("offsets", self.offsets_off, self.v, ow)?];
+        s.push(match self.is_compressed() {
+            true => section("neighbs", self.neighbs_off, self.neighbs_len, 1)?,
+            false => section("neighbs", self.neighbs_off, self.e, iw)?,
+        });
+        if self.is_weighted() {
+            s.push(section("weights", self.weights_off, self.e, ww)?);
+        }
+        if self.has_in_edges() {
+            s.push(section("in-edge offsets", self.in_offsets_off, self.v, ow)?);
+            s.push(section("in-edge neighbs", self.in_neighbs_off, self.e, iw)?);
+            if self.is_weighted() {
+                s.push(section("in-edge weights", self.in_weights_off, self.e, ww)?);
+            }
+        }
+        Some(s)
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_BYTES] {
+        let mut words = [0u64; HEADER_WORDS];
+        words[MAGIC_WORD] = u64::from_ne_bytes(MAGIC);
+        words[VERSION_WORD] = self.version;
+        words[FLAGS_WORD] = self.flags;
+        words[V_WORD] = self.v as u64;
+        words[E_WORD] = self.e as u64;
+        words[OFFSETS_WORD] = self.offsets_off as u64;
+        words[NEIGHBS_WORD] = self.neighbs_off as u64;
+        words[WEIGHTS_WORD] = self.weights_off as u64;
+        words[IN_OFFSETS_WORD] = self.in_offsets_off as u64;
+        words[IN_NEIGHBS_WORD] = self.in_neighbs_off as u64;
+        words[IN_WEIGHTS_WORD] = self.in_weights_off as u64;
+        words[NEIGHBS_LEN_WORD] = self.neighbs_len as u64;
+
+        let mut bytes = [0u8; HEADER_BYTES];
+        bytes
+            .chunks_mut(8)
+            .zip(words.iter())
+            .for_each(|(b, w)| b.copy_from_slice(&w.to_ne_bytes()));
+        bytes
+    }
+
+    /// Total size in bytes of a file holding this header and its sections,
+    /// or None if the header's counts are too large to describe any file
+    pub fn file_len(&self) -> Option<usize> {
+        self.sections()?.iter().map(|s| s.2).max()
+    }
+
+    pub fn is_weighted(&self) -> bool {
+        self.flags & FLAG_WEIGHTED != 0
+    }
+
+    pub fn is_sorted(&self) -> bool {
+        self.flags & FLAG_SORTED != 0
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.flags & FLAG_DIRECTED != 0
+    }
+
+    pub fn has_in_edges(&self) -> bool {
+        self.flags & FLAG_IN_EDGES != 0
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.flags & FLAG_COMPRESSED != 0
+    }
+
+    pub fn has_references(&self) -> bool {
+        self.flags & FLAG_REFERENCES != 0
+    }
+
+    pub fn id_width(&self) -> usize {
+        ((self.flags >> ID_WIDTH_SHIFT) & WIDTH_MASK) as usize
+    }
+
+    pub fn offset_width(&self) -> usize {
+        ((self.flags >> OFFSET_WIDTH_SHIFT) & WIDTH_MASK) as usize
+    }
+
+    /// The weight::KIND_* tag of the weights section, 0 if unweighted
+    pub fn weight_kind(&self) -> u64 {
+        match self.is_weighted() {
+            true => (self.flags >> WEIGHT_KIND_SHIFT) & WIDTH_MASK,
+            false => 0,
+        }
+    }
+
+    /// Width in bytes of one weight, 0 if unweighted
+    pub fn weight_width(&self) -> usize {
+        weight::kind_width(self.weight_kind()).unwrap_or(0)
+    }
+}
+
+/*Numbers the temporary files of this process so concurrent writers of the
+same destination do not collide*/
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/*A graph file being written.  Writers fill in a temporary file next to the
+destination and commit() syncs it and renames it into place, so a reader
+opening the destination sees either the old file or the complete new one,
+never a half-written graph.  If the writer fails before commit(), the
+temporary file is removed.*/
+pub(crate) struct AtomicFile {
+    tmp: PathBuf,
+    dst: PathBuf,
+    done: bool,
+}
+
+impl AtomicFile {
+    pub(crate) fn new<P: AsRef<Path>>(dst: P) -> AtomicFile {
+        let dst = dst.as_ref().to_path_buf();
+        let name = dst.file_name().map_or_else(Default::default, |n| n.to_string_lossy());
+        let tmp = dst.with_file_name(format!(
+            ".{}.raphy-tmp-{}-{}",
+            name,
+            std::process::id(),
+            TEMP_FILES.fetch_add(1, Ordering::SeqCst)
+        ));
+        AtomicFile {
+            tmp,
+            dst,
+            done: false,
+        }
+    }
+
+    /// Create (or truncate) the temporary file, open for reading and writing
+    pub(crate) fn create(&self) -> RaphyResult<File> {
+        Ok(fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.tmp)?)
+    }
+
+    /// Create the temporary file and write it through f, buffered, handing
+    /// back the finished file to commit
+    pub(crate) fn write(&self, f: impl FnOnce(&mut BufWriter<File>) -> RaphyResult<()>) -> RaphyResult<File> {
+        let mut w = BufWriter::new(self.create()?);
+        f(&mut w)?;
+        w.into_inner().map_err(|e| Error::Io(e.into_error()))
+    }
+
+    /// Sync the finished file to disk and move it to its destination
+    pub(crate) fn commit(mut self, file: File) -> RaphyResult<()> {
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&self.tmp, &self.dst)?;
+        self.done = true;
+        Ok(())
+    }
+}
+
+/*Write a file at path through f, under a temporary name that is renamed
+to path once the file is complete*/
+pub(crate) fn write_file<P: AsRef<Path>>(
+    path: P,
+    f: impl FnOnce(&mut BufWriter<File>) -> RaphyResult<()>,
+) -> RaphyResult<()> {
+    let out = AtomicFile::new(path);
+    let file = out.write(f)?;
+    out.commit(file)
+}
+
+/*Commit two files that only make sense together, e.g., a graph and its
+labels.  The old file at the first destination, if there is one, is kept
+under a temporary name (a hard link, or a copy where links are not
+supported) until the second commit succeeds; if it fails, the old file is
+moved back, or the new one removed if there was none, so the pair at the
+destinations is never half replaced.*/
+pub(crate) fn commit_pair(first: (AtomicFile, File), second: (AtomicFile, File)) -> RaphyResult<()> {
+    let dst = first.0.dst.clone();
+    let backup = AtomicFile::new(&dst);
+    let kept = match fs::hard_link(&dst, &backup.tmp) {
+        Ok(()) => true,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+        Err(_) => {
+            fs::copy(&dst, &backup.tmp)?;
+            true
+        }
+    };
+
+    first.0.commit(first.1)?;
+    if let Err(e) = second.0.commit(second.1) {
+        match kept {
+            true => {
+                let old = File::open(&backup.tmp)?;
+                backup.commit(old)?
+            }
+            false => fs::remove_file(&dst)?,
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = fs::remove_file(&self.tmp);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  Galois .gr files, the binary CSR format of the Galois system.  All words
+  are little-endian:
+
+  |version|edge data size|v|e|      four 8-byte words
+  |out index|                       v 8-byte words, the END of each vertex's
+                                    range of edges
+  |out edges|                       e 4-byte (version 1) or 8-byte
+                                    (version 2) neighbor ids, padded to 8
+                                    bytes in version 1
+  |edge data|                       e values of the edge data size, if it
+                                    is not 0
+
+  Every section starts on an 8-byte boundary, so a mapped file is used in
+  place, like a FastCSR.
+*/
+
+use byte_slice_cast::*;
+use memmap2::Mmap;
+use rayon::prelude::*;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::error::{Error, FormatError, Result};
+use crate::fast_csr::{self, Validation};
+use crate::index::Idx;
+use crate::weight::Weight;
+use crate::PropTrait;
+
+const HEADER_LEN: usize = 32;
+
+/*Offset of the edge data of a file with v vertices, e edges, and ids of
+width iw*/
+pub(crate) fn edge_data_off(v: usize, e: usize, iw: usize) -> Option<usize> {
+    let outs = e.checked_mul(iw)?;
+    v.checked_mul(8)?
+        .checked_add(HEADER_LEN)?
+        .checked_add((outs + 7) & !7)
+}
+
+/// A graph mapped from a Galois .gr file.  Neighbor ids are read as I: u32
+/// for a version 1 file, u64 for a version 2 file.
+pub struct GaloisGraph<I = u32> {
+    v: usize,
+    e: usize,
+    edge_size: usize,
+    nbase: usize,
+    wbase: usize,
+    raw: Box<Mmap>,
+    index: PhantomData<I>,
+}
+
+impl GaloisGraph {
+    /// Map a version 1 .gr file (4-byte ids), checking the whole file for
+    /// consistency (in parallel) before returning it
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<GaloisGraph> {
+        GaloisGraph::open_as(path, Validation::Parallel)
+    }
+
+    /// Map a version 1 .gr file, checking as much of it as the validation
+    /// level asks for
+    pub fn open_with<P: AsRef<Path>>(path: P, check: Validation) -> Result<GaloisGraph> {
+        GaloisGraph::open_as(path, check)
+    }
+}
+
+impl<I: Idx> GaloisGraph<I> {
+    /// Map a .gr file whose ids are I: u32 for version 1, u64 for version 2.
+    /// Fails with IndexWidth if the file's ids are the other width.
+    pub fn open_as<P: AsRef<Path>>(path: P, check: Validation) -> Result<GaloisGraph<I>> {
+        if cfg!(target_endian = "big") {
+            return Err(Error::Format(FormatError::ByteOrder));
+        }
+        let raw = fast_csr::map(path.as_ref())?;
+        if raw.len() < HEADER_LEN {
+            return Err(Error::Format(FormatError::Truncated {
+                len: raw.len(),
+                need: HEADER_LEN,
+            }));
+        }
+        let words = raw[..HEADER_LEN].as_slice_of::<u64>().unwrap();
+        let id = match words[0] {
+            1 => 4,
+            2 => 8,
+            version => return Err(Error::Format(FormatError::UnsupportedVersion(version))),
+        };
+        if id != I::WIDTH {
+            return Err(Error::Format(FormatError::IndexWidth { id, offset: 8 }));
+        }
+
+        let (edge_size, v, e) = (words[1] as usize, words[2] as usize, words[3] as usize);
+        let too_large = || Error::Format(FormatError::TooLarge { v, e });
+        let wbase = edge_data_off(v, e, id).ok_or_else(too_large)?;
+        let need = e
+            .checked_mul(edge_size)
+            .and_then(|w| w.checked_add(wbase))
+            .ok_or_else(too_large)?;
+        if raw.len() < need {
+            return Err(Error::Format(FormatError::Truncated { len: raw.len(), need }));
+        }
+
+        let g = GaloisGraph {
+            v,
+            e,
+            edge_size,
+            nbase: HEADER_LEN + v * 8,
+            wbase,
+            raw,
+            index: PhantomData,
+        };
+
+        match check {
+            Validation::Header => (),
+            Validation::Sequential => g.validate(false)?,
+            Validation::Parallel => g.validate(true)?,
+        }
+
+        Ok(g)
+    }
+
+    /// Check that the out index is non-decreasing and ends at e, and that
+    /// every neighbor id is less than v.  Reports the first problem found.
+    pub fn validate(&self, parallel: bool) -> Result<()> {
+        let ends = self.ends();
+        let bad_end = |i: &usize| *i > 0 && ends[*i] < ends[*i - 1];
+        let bad = match parallel {
+            true => (0..self.v).into_par_iter().find_first(bad_end),
+            false => (0..self.v).find(bad_end),
+        };
+        if let Some(vtx) = bad {
+            return Err(Error::Format(FormatError::OffsetsNotMonotonic { vtx }));
+        }
+        let last = ends.last().map_or(0, |l| *l as usize);
+        if last != self.e {
+            return Err(Error::Format(FormatError::EdgeCount { count: last, e: self.e }));
+        }
+
+        let edges = self.edges();
+        let bad_neighbor = |i: &usize| edges[*i].index() >= self.v;
+        let bad = match parallel {
+            true => (0..self.e).into_par_iter().find_first(bad_neighbor),
+            false => (0..self.e).find(bad_neighbor),
+        };
+        if let Some(i) = bad {
+            /*The vertex owning edges[i] is the first one whose range ends past i*/
+            let vtx = ends.partition_point(|end| *end as usize <= i);
+            return Err(Error::Format(FormatError::NeighborOutOfRange {
+                vtx,
+                neighbor: edges[i].index(),
+                v: self.v,
+            }));
+        }
+
+        Ok(())
+    }
+
+    fn ends(&self) -> &[u64] {
+        self.raw[HEADER_LEN..self.nbase].as_slice_of::<u64>().unwrap()
+    }
+
+    fn edges(&self) -> &[I] {
+        self.raw[self.nbase..self.nbase + self.e * I::WIDTH]
+            .as_slice_of::<I>()
+            .unwrap()
+    }
+
+    pub fn getv(&self) -> usize {
+        self.v
+    }
+
+    pub fn gete(&self) -> usize {
+        self.e
+    }
+
+    /// Size in bytes of each edge's data, 0 if the edges have none
+    pub fn edge_data_size(&self) -> usize {
+        self.edge_size
+    }
+
+    fn vtx_offset_range(&self, v: usize) -> (usize, usize) {
+        let ends = self.ends();
+        let st = if v == 0 { 0 } else { ends[v - 1] as usize };
+        (st, ends[v] as usize)
+    }
+
+    pub fn neighbors(&self, v: usize) -> &[I] {
+        let (st, en) = self.vtx_offset_range(v);
+        &self.edges()[st..en]
+    }
+
+    /// The number of edges out of vertex v
+    pub fn out_degree(&self, v: usize) -> usize {
+        let (st, en) = self.vtx_offset_range(v);
+        en - st
+    }
+
+    /// Check that the edges carry data that can be read as weights of type
+    /// W.  Galois records only the size of edge data, so a W of the right
+    /// width is all that can be checked.
+    pub fn check_weights<W: Weight>(&self) -> Result<()> {
+        if self.edge_size == 0 {
+            return Err(Error::Format(FormatError::MissingSection("edge data")));
+        }
+        let width = std::mem::size_of::<W>();
+        if self.edge_size != width {
+            return Err(Error::Format(FormatError::WeightWidth {
+                expected: width,
+                found: self.edge_size,
+            }));
+        }
+        Ok(())
+    }
+
+    fn weights<W: Weight>(&self) -> &[W] {
+        if let Err(e) = self.check_weights::<W>() {
+            panic!("{}", e);
+        }
+        self.raw[self.wbase..self.wbase + self.e * self.edge_size]
+            .as_slice_of::<W>()
+            .unwrap()
+    }
+
+    /// The neighbors of vertex v and the weights of the edges to them.
+    /// Panics if check_weights::<W> fails.
+    pub fn weighted_neighbors<W: Weight>(&self, v: usize) -> (&[I], &[W]) {
+        let (st, en) = self.vtx_offset_range(v);
+        (&self.edges()[st..en], &self.weights()[st..en])
+    }
+
+    /// Set each vertex's property to f of the vertex and its neighbors, in parallel
+    pub fn neighbor_scan_prop<P: PropTrait>(
+        &self,
+        f: impl Fn(usize, &[I]) -> P + std::marker::Sync,
+        prop: &mut [P],
+    ) {
+        prop.par_iter_mut()
+            .enumerate()
+            .for_each(|(v, p)| *p = f(v, self.neighbors(v)));
+    }
+
+    /// Apply f to every edge (v0,v1), in parallel across vertices
+    pub fn read_only_scan(&self, f: impl Fn(usize, usize) + std::marker::Sync) {
+        (0..self.v).into_par_iter().for_each(|v| {
+            for n in self.neighbors(v) {
+                f(v, n.index());
+            }
+        });
+    }
+} /*impl GaloisGraph*/
@@ -10,6 +10,16 @@ WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 See the License for the specific language governing permissions and
 limitations under the License.
 */
+
+#![allow(
+    clippy::assign_op_pattern,
+    clippy::bool_comparison,
+    clippy::explicit_auto_deref,
+    clippy::needless_return,
+    clippy::new_without_default,
+    clippy::replace_box,
+)]
+
 use crate::vertex::Vertex;
 use crate::VtxTrait;
 
@@ -0,0 +1,95 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use byte_slice_cast::{FromByteSlice, ToByteSlice};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+
+/// An unsigned integer type that can hold the vertex ids (neighbs) or the
+/// edge offsets (offsets) of a CSR.  Graphs with fewer than 2^32 vertices,
+/// or edges, can store them as u32 and take half the memory and bandwidth.
+pub trait Idx:
+    Copy
+    + Default
+    + Ord
+    + Send
+    + Sync
+    + std::hash::Hash
+    + std::fmt::Debug
+    + std::fmt::Display
+    + FromByteSlice
+    + ToByteSlice
+    + 'static
+{
+    /// Width in bytes, as recorded in a FastCSR header
+    const WIDTH: usize;
+
+    /// The atomic type of the same width, for filling slots in parallel
+    type Atomic: Sync;
+
+    /// Convert from usize.  The value must fit (see fits).
+    fn from_usize(v: usize) -> Self;
+
+    /// Convert to usize, to index with
+    fn index(self) -> usize;
+
+    /// True if v can be stored in this type
+    fn fits(v: usize) -> bool;
+
+    /// View a slice as atomics, so threads can fill disjoint slots of it
+    fn atomic_slice(s: &mut [Self]) -> &[Self::Atomic];
+
+    fn store(a: &Self::Atomic, v: Self);
+}
+
+macro_rules! idx {
+    ($t:ty, $atomic:ty) => {
+        impl Idx for $t {
+            const WIDTH: usize = std::mem::size_of::<$t>();
+
+            type Atomic = $atomic;
+
+            fn from_usize(v: usize) -> $t {
+                v as $t
+            }
+
+            fn index(self) -> usize {
+                self as usize
+            }
+
+            fn fits(v: usize) -> bool {
+                v as u128 <= <$t>::MAX as u128
+            }
+
+            fn atomic_slice(s: &mut [$t]) -> &[$atomic] {
+                /*The atomic type has the same in-memory representation as
+                the integer, and the &mut guarantees nothing else is looking
+                at the slice*/
+                unsafe { &*(s as *mut [$t] as *const [$atomic]) }
+            }
+
+            fn store(a: &$atomic, v: $t) {
+                a.store(v, Ordering::Relaxed);
+            }
+        }
+    };
+}
+
+idx!(u32, AtomicU32);
+idx!(u64, AtomicU64);
+idx!(usize, AtomicUsize);
+
+/// True if a graph with v vertices and e edges can keep its ids as I and
+/// its offsets as O
+pub fn fits<I: Idx, O: Idx>(v: usize, e: usize) -> bool {
+    (v == 0 || I::fits(v - 1)) && O::fits(e)
+}
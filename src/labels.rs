@@ -0,0 +1,330 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  Vertex labels: the names (strings, or sparse 64-bit ids written as text)
+  an edge list gives its vertices, and the dense ids 0..v a CSR gives them
+  instead.  A label file is kept alongside a FastCSR file, in the byte order
+  of the machine that wrote it, laid out as 8-byte words:
+
+  |magic|version|n|label bytes|
+  |offsets: n + 1 words, into the label bytes, in id order|
+  |sorted: n words, the ids in the order of their labels|
+  |label bytes: every label, in id order|
+
+  so an id's label is found by indexing the offsets, and a label's id by
+  binary search on the sorted table, both straight from the mapped file.
+*/
+
+use byte_slice_cast::*;
+use memmap2::Mmap;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::csr::{BuildOptions, EdgeList, WeightedEdgeList, CSR};
+use crate::error::{Error, FormatError, Result};
+use crate::format::{self, AtomicFile};
+use crate::text_el::{self, TextFormat};
+use crate::weight::Weight;
+
+pub const MAGIC: [u8; 8] = *b"RAPHYLBL";
+pub const VERSION: u64 = 1;
+const HEADER_WORDS: usize = 4;
+
+/// Where the labels of the FastCSR file at path are kept: the same path
+/// with ".labels" appended
+pub fn labels_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut p = path.as_ref().as_os_str().to_owned();
+    p.push(".labels");
+    PathBuf::from(p)
+}
+
+/*Hands out dense ids to labels in the order they are first seen*/
+#[derive(Default)]
+struct Relabeler {
+    ids: HashMap<String, usize>,
+}
+
+impl Relabeler {
+    fn id(&mut self, label: String) -> usize {
+        let next = self.ids.len();
+        *self.ids.entry(label).or_insert(next)
+    }
+
+    /*Every label, indexed by its id*/
+    fn into_labels(self) -> Vec<String> {
+        let mut labels = vec![String::new(); self.ids.len()];
+        for (l, i) in self.ids {
+            labels[i] = l;
+        }
+        labels
+    }
+}
+
+/// Read a text edge list, laid out as fmt says, whose first two fields are
+/// vertex labels rather than ids.  Each label is given the next dense id
+/// the first time it appears, so the ids follow the order of the file.
+/// Returns the labels, indexed by id, and the edge list.  fmt.one_based
+/// does not apply.
+pub fn read_labeled_el<Q: AsRef<Path>>(path: Q, fmt: &TextFormat) -> Result<(Vec<String>, EdgeList)> {
+    let named = text_el::read(path.as_ref(), fmt, |f| match f {
+        [v0, v1, ..] => Ok((v0.to_string(), v1.to_string())),
+        _ => Err("missing vertex label".to_string()),
+    })?;
+
+    let mut r = Relabeler::default();
+    let el = named.into_iter().map(|(v0, v1)| (r.id(v0), r.id(v1))).collect();
+    Ok((r.into_labels(), el))
+}
+
+/// Like read_labeled_el, with the weight in fmt's weight column
+pub fn read_labeled_wel<W: Weight, Q: AsRef<Path>>(
+    path: Q,
+    fmt: &TextFormat,
+) -> Result<(Vec<String>, WeightedEdgeList<W>)> {
+    let col = fmt.weight_column.unwrap_or(2);
+    let named = text_el::read(path.as_ref(), fmt, |f| {
+        let s = f.get(col).ok_or_else(|| "missing weight".to_string())?;
+        let w = W::parse_weight(s).ok_or_else(|| format!("bad weight {:?}", s))?;
+        Ok((f[0].to_string(), f[1].to_string(), w))
+    })?;
+
+    let mut r = Relabeler::default();
+    let el = named
+        .into_iter()
+        .map(|(v0, v1, w)| (r.id(v0), r.id(v1), w))
+        .collect();
+    Ok((r.into_labels(), el))
+}
+
+/// Convert a text edge list with labeled vertices (see read_labeled_el)
+/// into a FastCSR file at out, built as the options describe, with its
+/// labels written to labels_path(out).  Both files are written in full
+/// before either is moved into place, and the graph is put back if the
+/// labels cannot be, so a failure leaves any earlier graph and labels at
+/// out as they were.  Returns the opened labels.
+pub fn labeled_text_to_fastcsr<P: AsRef<Path>, Q: AsRef<Path>>(
+    path: P,
+    out: Q,
+    fmt: &TextFormat,
+    opts: &BuildOptions,
+) -> Result<Labels> {
+    let (labels, el) = read_labeled_el(path, fmt)?;
+    let csr = CSR::new_with(labels.len(), el, opts);
+
+    let lpath = labels_path(&out);
+    let gout = AtomicFile::new(out);
+    let lout = AtomicFile::new(&lpath);
+    let gfile = csr.write_fastcsr_to(&gout)?;
+    let lfile = Labels::write_to(&lout, &labels)?;
+    format::commit_pair((gout, gfile), (lout, lfile))?;
+    Labels::open(lpath)
+}
+
+/// A mapped label file, translating between dense vertex ids and the
+/// labels they were read with
+pub struct Labels {
+    mmap: Mmap,
+    n: usize,
+    sorted_off: usize,
+    bytes_off: usize,
+}
+
+impl Labels {
+    /// Write a label file at path for the vertices labeled labels[0],
+    /// labels[1], ...  Fails with BadLabel if a label repeats.
+    pub fn write<S: AsRef<str> + Sync, Q: AsRef<Path>>(path: Q, labels: &[S]) -> Result<()> {
+        let out = AtomicFile::new(path);
+        let file = Labels::write_to(&out, labels)?;
+        out.commit(file)
+    }
+
+    /*Write the label file into out's temporary file, leaving the caller to
+    commit it*/
+    fn write_to<S: AsRef<str> + Sync>(out: &AtomicFile, labels: &[S]) -> Result<File> {
+        let n = labels.len();
+        let mut sorted: Vec<usize> = (0..n).collect();
+        sorted.par_sort_unstable_by_key(|i| labels[*i].as_ref());
+        if let Some(w) = sorted
+            .windows(2)
+            .find(|w| labels[w[0]].as_ref() == labels[w[1]].as_ref())
+        {
+            return Err(FormatError::BadLabel { vtx: w[0].max(w[1]) }.into());
+        }
+
+        let mut offsets = Vec::with_capacity(n + 1);
+        offsets.push(0);
+        for l in labels {
+            offsets.push(offsets[offsets.len() - 1] + l.as_ref().len());
+        }
+        let header = [u64::from_ne_bytes(MAGIC), VERSION, n as u64, offsets[n] as u64];
+
+        out.write(|w| {
+            w.write_all(header.as_byte_slice())?;
+            w.write_all(offsets.as_byte_slice())?;
+            w.write_all(sorted.as_byte_slice())?;
+            for l in labels {
+                w.write_all(l.as_ref().as_bytes())?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Map the label file at path, checking that every label is UTF-8
+    /// and the sorted table really is sorted
+    pub fn open<Q: AsRef<Path>>(path: Q) -> Result<Labels> {
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        let head = HEADER_WORDS * 8;
+        if mmap.len() < head {
+            return Err(FormatError::Truncated { len: mmap.len(), need: head }.into());
+        }
+        let words = mmap[..head].as_slice_of::<u64>().unwrap();
+        if words[0].to_ne_bytes() != MAGIC {
+            return Err(match words[0].swap_bytes().to_ne_bytes() == MAGIC {
+                true => FormatError::ByteOrder,
+                false => FormatError::BadMagic,
+            }
+            .into());
+        }
+        if words[1] != VERSION {
+            return Err(FormatError::UnsupportedVersion(words[1]).into());
+        }
+
+        let (n, nbytes) = (words[2] as usize, words[3] as usize);
+        let too_large = || FormatError::TooLarge { v: n, e: nbytes };
+        let sorted_off = n
+            .checked_add(1)
+            .and_then(|w| w.checked_mul(8))
+            .and_then(|b| b.checked_add(head))
+            .ok_or_else(too_large)?;
+        let bytes_off = n
+            .checked_mul(8)
+            .and_then(|b| b.checked_add(sorted_off))
+            .ok_or_else(too_large)?;
+        let need = bytes_off.checked_add(nbytes).ok_or_else(too_large)?;
+        if mmap.len() < need {
+            return Err(FormatError::Truncated { len: mmap.len(), need }.into());
+        }
+
+        let labels = Labels {
+            mmap,
+            n,
+            sorted_off,
+            bytes_off,
+        };
+        labels.validate(nbytes)?;
+        Ok(labels)
+    }
+
+    fn validate(&self, nbytes: usize) -> Result<()> {
+        let offsets = self.offsets();
+        if offsets[0] != 0 {
+            return Err(FormatError::FirstOffsetNonZero(offsets[0]).into());
+        }
+        if let Some(v) = (1..=self.n).into_par_iter().find_first(|v| offsets[*v] < offsets[*v - 1]) {
+            return Err(FormatError::OffsetsNotMonotonic { vtx: v }.into());
+        }
+        if offsets[self.n] != nbytes {
+            return Err(FormatError::OffsetOutOfRange {
+                vtx: self.n,
+                offset: offsets[self.n],
+                e: nbytes,
+            }
+            .into());
+        }
+
+        let bytes = &self.mmap[self.bytes_off..self.bytes_off + nbytes];
+        if let Some(v) = (0..self.n)
+            .into_par_iter()
+            .find_first(|v| std::str::from_utf8(&bytes[offsets[*v]..offsets[*v + 1]]).is_err())
+        {
+            return Err(FormatError::BadLabel { vtx: v }.into());
+        }
+
+        /*Strictly increasing labels, each belonging to an id < n, make the
+        table a sorted permutation of the ids*/
+        let sorted = self.sorted();
+        if let Some(v) = sorted.par_iter().find_first(|v| **v >= self.n) {
+            return Err(FormatError::BadLabel { vtx: *v }.into());
+        }
+        if let Some(i) = (1..self.n)
+            .into_par_iter()
+            .find_first(|i| self.label(sorted[*i - 1]) >= self.label(sorted[*i]))
+        {
+            return Err(FormatError::BadLabel { vtx: sorted[i] }.into());
+        }
+        Ok(())
+    }
+
+    fn offsets(&self) -> &[usize] {
+        self.mmap[HEADER_WORDS * 8..self.sorted_off]
+            .as_slice_of::<usize>()
+            .unwrap()
+    }
+
+    fn sorted(&self) -> &[usize] {
+        self.mmap[self.sorted_off..self.bytes_off]
+            .as_slice_of::<usize>()
+            .unwrap()
+    }
+
+    /// The number of labeled vertices
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// The label of vertex id.  Panics if id is out of range.
+    pub fn label(&self, id: usize) -> &str {
+        let offsets = self.offsets();
+        let bytes = &self.mmap[self.bytes_off + offsets[id]..self.bytes_off + offsets[id + 1]];
+        /*Checked to be UTF-8 when the file was opened*/
+        std::str::from_utf8(bytes).unwrap()
+    }
+
+    /// The id of the vertex with the given label, if there is one
+    pub fn id(&self, label: &str) -> Option<usize> {
+        let sorted = self.sorted();
+        sorted
+            .binary_search_by(|i| self.label(*i).cmp(label))
+            .ok()
+            .map(|at| sorted[at])
+    }
+
+    /// Write a per-vertex result, e.g., a BFS depth or a PageRank, as text
+    /// with one "label<TAB>value" line per vertex, in id order.  Fails with
+    /// an InvalidInput i/o error if there is not exactly one value per
+    /// vertex.
+    pub fn write_values<T: Display, Q: AsRef<Path>>(&self, path: Q, values: &[T]) -> Result<()> {
+        if values.len() != self.n {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} values for {} labeled vertices", values.len(), self.n),
+            )));
+        }
+        format::write_file(path, |w| {
+            for (v, x) in values.iter().enumerate() {
+                writeln!(w, "{}\t{}", self.label(v), x)?;
+            }
+            Ok(())
+        })
+    }
+} /*impl Labels*/
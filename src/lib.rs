@@ -11,10 +11,35 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+pub use error::{Error, Result};
+
 pub trait VtxTrait: Ord + std::fmt::Debug + std::fmt::Display {}
 impl<T> VtxTrait for T where T: Ord + std::fmt::Debug + std::fmt::Display {}
 
+pub trait PropTrait: Copy + Send + Sync {}
+impl<T> PropTrait for T where T: Copy + Send + Sync {}
+
+pub mod bfs;
+pub mod bvgraph;
+pub mod compressed;
+pub mod convert;
 pub mod csr;
+pub mod dfs;
+pub mod dimacs;
+pub mod error;
+pub mod export;
+pub mod external;
 pub mod fast_csr;
+pub mod format;
+pub mod galois;
 pub mod graph;
+pub mod index;
+pub mod labels;
+pub mod ligra;
+pub mod metis;
+pub mod mtx;
+mod place;
+pub mod text_el;
+pub mod traverse;
 pub mod vertex;
+pub mod weight;
@@ -0,0 +1,195 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  Ligra's AdjacencyGraph text format, also read by GBBS: a CSR written out
+  one value per line,
+
+  AdjacencyGraph
+  <n>
+  <m>
+  <offset of vertex 0>
+  ...
+  <offset of vertex n - 1>
+  <neighbor 0>
+  ...
+  <neighbor m - 1>
+
+  with 0-based ids.  A WeightedAdjacencyGraph has the m edge weights after
+  the neighbors.  Any whitespace between values is accepted.
+*/
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+
+use crate::csr::{EdgeList, WeightedEdgeList};
+use crate::error::{Error, Result};
+use crate::weight::Weight;
+
+pub const HEADER: &str = "AdjacencyGraph";
+pub const WEIGHTED_HEADER: &str = "WeightedAdjacencyGraph";
+
+/*The whitespace separated values of a file, with the lines they are on*/
+struct Tokens {
+    lines: Lines<BufReader<File>>,
+    line: u64,
+    words: Vec<String>,
+}
+
+impl Tokens {
+    fn next(&mut self) -> Result<Option<(u64, String)>> {
+        while self.words.is_empty() {
+            match self.lines.next() {
+                Some(l) => {
+                    self.line += 1;
+                    self.words = l?.split_whitespace().rev().map(String::from).collect();
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(self.words.pop().map(|w| (self.line, w)))
+    }
+
+    /*The next value, which has to be there, as a T*/
+    fn expect<T>(&mut self, what: &str, parse: impl Fn(&str) -> Option<T>) -> Result<(u64, T)> {
+        match self.next()? {
+            Some((line, w)) => match parse(&w) {
+                Some(x) => Ok((line, x)),
+                None => Err(Error::parse(line, format!("bad {} {:?}", what, w))),
+            },
+            None => Err(Error::parse(self.line, format!("file ends before the last {}", what))),
+        }
+    }
+}
+
+/*The sections of a file; weights is empty unless they were asked for*/
+struct Parsed<T> {
+    n: usize,
+    offsets: Vec<usize>,
+    neighbs: Vec<usize>,
+    weights: Vec<T>,
+}
+
+/*Read the AdjacencyGraph file at path, parsing the weights with weight if
+it is given.  Fails if weights are asked for and the file has none.*/
+fn read<T>(path: &str, weight: Option<impl Fn(&str) -> Option<T>>) -> Result<Parsed<T>> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut t = Tokens {
+        lines: BufReader::new(file).lines(),
+        line: 0,
+        words: Vec::new(),
+    };
+
+    let (line, header) = t.expect("header", |w| Some(w.to_string()))?;
+    let weighted = match header.as_str() {
+        HEADER => false,
+        WEIGHTED_HEADER => true,
+        _ => return Err(Error::parse(line, format!("expected {} or {}", HEADER, WEIGHTED_HEADER))),
+    };
+    if weight.is_some() && !weighted {
+        return Err(Error::parse(line, "graph has no weights"));
+    }
+
+    let count = |w: &str| w.parse::<usize>().ok();
+    let (_, n) = t.expect("vertex count", count)?;
+    let (line, m) = t.expect("edge count", count)?;
+
+    /*Every offset and neighbor takes at least a byte, so counts larger
+    than the file are corrupt; checking them first keeps the reservations
+    below from asking for more memory than the file could fill*/
+    if n.checked_add(m).is_none_or(|c| c as u64 > len) {
+        return Err(Error::parse(
+            line,
+            format!("{} vertices and {} edges do not fit in a {} byte file", n, m, len),
+        ));
+    }
+
+    let mut offsets = Vec::with_capacity(n);
+    for v in 0..n {
+        let (line, o) = t.expect("offset", count)?;
+        let prev = offsets.last().copied().unwrap_or(0);
+        if (v == 0 && o != 0) || o < prev || o > m {
+            return Err(Error::parse(line, format!("offset {} of vertex {} is out of order", o, v)));
+        }
+        offsets.push(o);
+    }
+
+    let mut neighbs = Vec::with_capacity(m);
+    for _ in 0..m {
+        let (_, x) = t.expect("neighbor", count)?;
+        if x >= n {
+            return Err(Error::VertexOutOfRange { vtx: x, v: n });
+        }
+        neighbs.push(x);
+    }
+
+    let mut weights = Vec::new();
+    if weighted {
+        match &weight {
+            Some(parse) => {
+                weights.reserve(m);
+                for _ in 0..m {
+                    weights.push(t.expect("weight", parse)?.1);
+                }
+            }
+            None => {
+                for _ in 0..m {
+                    t.expect("weight", |_| Some(()))?;
+                }
+            }
+        }
+    }
+
+    if let Some((line, w)) = t.next()? {
+        return Err(Error::parse(line, format!("unexpected value {:?} after the graph", w)));
+    }
+    Ok(Parsed {
+        n,
+        offsets,
+        neighbs,
+        weights,
+    })
+}
+
+/*The vertex whose edge is neighbs[i], walking the offsets along with i*/
+fn sources(offsets: &[usize], m: usize) -> impl Iterator<Item = usize> + '_ {
+    (0..offsets.len()).flat_map(move |v| {
+        let end = offsets.get(v + 1).copied().unwrap_or(m);
+        std::iter::repeat_n(v, end - offsets[v])
+    })
+}
+
+/// Read the edges of the AdjacencyGraph (or WeightedAdjacencyGraph) file at
+/// path, ignoring any weights.  Returns the number of vertices and the edge
+/// list.  Fails if the file cannot be read, a value does not parse, the
+/// header counts more values than the file can hold, an offset is out of
+/// order, or a neighbor is out of range.
+pub fn read_el(path: &str) -> Result<(usize, EdgeList)> {
+    let g = read::<()>(path, None::<fn(&str) -> Option<()>>)?;
+    let el = sources(&g.offsets, g.neighbs.len()).zip(g.neighbs.iter().copied()).collect();
+    Ok((g.n, el))
+}
+
+/// Read the edges of the WeightedAdjacencyGraph file at path with their
+/// weights as type W.  Fails as read_el does, or if the file is not
+/// weighted or a weight does not parse as a W.
+pub fn read_wel<W: Weight>(path: &str) -> Result<(usize, WeightedEdgeList<W>)> {
+    let g = read(path, Some(W::parse_weight))?;
+    let el = sources(&g.offsets, g.neighbs.len())
+        .zip(g.neighbs.iter().copied())
+        .zip(g.weights)
+        .map(|((v0, v1), w)| (v0, v1, w))
+        .collect();
+    Ok((g.n, el))
+}
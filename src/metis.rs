@@ -0,0 +1,190 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  METIS .graph files, the input format of the METIS and KaHIP partitioners:
+
+  % any number of comment lines
+  <n> <m> [fmt [ncon]]
+  <adjacency line of vertex 1>
+  ...
+  <adjacency line of vertex n>
+
+  The graph is undirected: m counts each edge once, but it is listed on the
+  lines of both of its ends.  Ids are 1-based.  fmt is up to three binary
+  digits: the last says each neighbor is followed by the edge's weight, the
+  middle that each line starts with ncon (default 1) vertex weights, and
+  the first that each line starts with a vertex size before those.  A blank
+  adjacency line is a vertex with no neighbors.
+*/
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::csr::{EdgeList, WeightedEdgeList};
+use crate::error::{Error, Result};
+use crate::weight::Weight;
+
+/// The header line of a METIS file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetisHeader {
+    pub v: usize,
+    /// The number of undirected edges, half the number of adjacency entries
+    pub edges: usize,
+    /// Each line starts with a vertex size
+    pub vertex_sizes: bool,
+    /// The number of vertex weights on each line
+    pub vertex_weights: usize,
+    /// Each neighbor is followed by the edge's weight
+    pub edge_weights: bool,
+}
+
+impl MetisHeader {
+    /// The header line, with fmt (and ncon) only if they are not the defaults
+    pub fn line(&self) -> String {
+        let fmt = format!(
+            "{}{}{}",
+            self.vertex_sizes as u8,
+            (self.vertex_weights > 0) as u8,
+            self.edge_weights as u8
+        );
+        match (fmt.as_str(), self.vertex_weights) {
+            ("000", _) => format!("{} {}", self.v, self.edges),
+            (_, 0) | (_, 1) => format!("{} {} {}", self.v, self.edges, fmt),
+            (_, ncon) => format!("{} {} {} {}", self.v, self.edges, fmt, ncon),
+        }
+    }
+}
+
+/*Parse a header line*/
+fn parse_header(line: u64, s: &str) -> Result<MetisHeader> {
+    let bad = || Error::parse(line, format!("bad header line {:?}", s));
+    let words: Vec<&str> = s.split_whitespace().collect();
+    if words.len() < 2 || words.len() > 4 {
+        return Err(bad());
+    }
+    let num = |w: &str| w.parse::<usize>().map_err(|_| bad());
+    let fmt = words.get(2).copied().unwrap_or("0");
+    if fmt.len() > 3 || !fmt.chars().all(|c| c == '0' || c == '1') {
+        return Err(bad());
+    }
+    let fmt = format!("{:0>3}", fmt).into_bytes();
+    let ncon = match words.get(3) {
+        Some(w) => num(w)?,
+        None => 1,
+    };
+    Ok(MetisHeader {
+        v: num(words[0])?,
+        edges: num(words[1])?,
+        vertex_sizes: fmt[0] == b'1',
+        vertex_weights: if fmt[1] == b'1' { ncon } else { 0 },
+        edge_weights: fmt[2] == b'1',
+    })
+}
+
+/*Read the METIS file at path, handing f the line number, 0-based ids, and
+weight field of each adjacency entry.  Vertex sizes and weights are
+skipped.  Fails on a file without edge weights if weights are needed.*/
+fn read(
+    path: &str,
+    weights: bool,
+    mut f: impl FnMut(u64, usize, usize, Option<&str>) -> Result<()>,
+) -> Result<MetisHeader> {
+    let mut line = 0u64;
+    let mut h: Option<MetisHeader> = None;
+    let (mut vtx, mut entries) = (0, 0);
+    for l in BufReader::new(File::open(path)?).lines() {
+        line += 1;
+        let l = l?;
+        if l.starts_with('%') {
+            continue;
+        }
+        let h = match h {
+            Some(h) => h,
+            None if l.trim().is_empty() => continue,
+            None => {
+                let parsed = parse_header(line, &l)?;
+                if weights && !parsed.edge_weights {
+                    return Err(Error::parse(line, "graph has no edge weights"));
+                }
+                h = Some(parsed);
+                continue;
+            }
+        };
+
+        /*Lines after the last vertex's may only be blank*/
+        if vtx == h.v {
+            if l.trim().is_empty() {
+                continue;
+            }
+            return Err(Error::parse(line, format!("more than {} adjacency lines", h.v)));
+        }
+
+        let words: Vec<&str> = l.split_whitespace().collect();
+        let skip = h.vertex_sizes as usize + h.vertex_weights;
+        let per = 1 + h.edge_weights as usize;
+        if words.len() < skip || !(words.len() - skip).is_multiple_of(per) {
+            return Err(Error::parse(line, "adjacency line has a missing field"));
+        }
+        for e in words[skip..].chunks(per) {
+            let n = e[0]
+                .parse::<usize>()
+                .map_err(|_| Error::parse(line, format!("bad vertex id {:?}", e[0])))?;
+            if n == 0 {
+                return Err(Error::parse(line, "vertex ids are 1-based"));
+            }
+            if n > h.v {
+                return Err(Error::VertexOutOfRange { vtx: n - 1, v: h.v });
+            }
+            f(line, vtx, n - 1, e.get(1).copied())?;
+            entries += 1;
+        }
+        vtx += 1;
+    }
+
+    /*Vertices at the end with no neighbors may have no lines at all*/
+    let h = h.ok_or_else(|| Error::parse(line, "missing header line"))?;
+    if entries != 2 * h.edges {
+        return Err(Error::parse(
+            line,
+            format!("expected {} adjacency entries for {} edges, found {}", 2 * h.edges, h.edges, entries),
+        ));
+    }
+    Ok(h)
+}
+
+/// Read the edges of the METIS file at path, each undirected edge as the
+/// two directed edges listed for it, ignoring any weights.  Fails if the
+/// file cannot be read, a line does not parse, an id is out of range, or
+/// the file does not list twice as many entries as its header has edges.
+pub fn read_el(path: &str) -> Result<(MetisHeader, EdgeList)> {
+    let mut el = Vec::new();
+    let h = read(path, false, |_, v0, v1, _| {
+        el.push((v0, v1));
+        Ok(())
+    })?;
+    Ok((h, el))
+}
+
+/// Like read_el, with the edge weights as weights of type W.  Fails if the
+/// file has no edge weights or one does not parse as a W.
+pub fn read_wel<W: Weight>(path: &str) -> Result<(MetisHeader, WeightedEdgeList<W>)> {
+    let mut el = Vec::new();
+    let h = read(path, true, |line, v0, v1, wt| {
+        let s = wt.unwrap_or_default();
+        let w = W::parse_weight(s).ok_or_else(|| Error::parse(line, format!("bad weight {:?}", s)))?;
+        el.push((v0, v1, w));
+        Ok(())
+    })?;
+    Ok((h, el))
+}
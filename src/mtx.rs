@@ -0,0 +1,233 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  Matrix Market coordinate files, as distributed by SuiteSparse:
+
+  %%MatrixMarket matrix coordinate <field> <symmetry>
+  % any number of comment lines
+  <rows> <cols> <entries>
+  <i> <j> [value]
+  ...
+
+  Ids are 1-based.  The field is pattern (no values), real, or integer, and
+  the symmetry general, or symmetric, in which case only one triangle is
+  listed and each off-diagonal entry (i,j) stands for (j,i) as well.  Entry
+  (i,j) is the edge from vertex i - 1 to vertex j - 1.
+*/
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::csr::WeightedEdgeList;
+use crate::error::{Error, Result};
+use crate::weight::{self, Weight};
+
+/// What a Matrix Market file stores for each entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// Only the position of each entry
+    Pattern,
+    /// A floating point value
+    Real,
+    /// An integer value
+    Integer,
+}
+
+impl Field {
+    /// The field a weight of type W is written as
+    pub fn of<W: Weight>() -> Field {
+        match W::KIND {
+            weight::KIND_F64 | weight::KIND_F32 => Field::Real,
+            _ => Field::Integer,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Field::Pattern => "pattern",
+            Field::Real => "real",
+            Field::Integer => "integer",
+        }
+    }
+}
+
+/// Which entries of a Matrix Market file are listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// Every entry is listed
+    General,
+    /// Only the entries on and below (or above) the diagonal are listed
+    Symmetric,
+}
+
+/// The banner and size line of a Matrix Market file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MtxHeader {
+    pub rows: usize,
+    pub cols: usize,
+    /// The number of entries listed, not counting the mirror images of a
+    /// symmetric file's entries
+    pub entries: usize,
+    pub field: Field,
+    pub symmetry: Symmetry,
+}
+
+impl MtxHeader {
+    /// The number of vertices of the graph: a non-square matrix's rows
+    /// and columns are the same vertices
+    pub fn numv(&self) -> usize {
+        self.rows.max(self.cols)
+    }
+
+    /// The banner line describing the file
+    pub fn banner(&self) -> String {
+        let sym = match self.symmetry {
+            Symmetry::General => "general",
+            Symmetry::Symmetric => "symmetric",
+        };
+        format!("%%MatrixMarket matrix coordinate {} {}", self.field.name(), sym)
+    }
+}
+
+/*Parse the banner on line 1*/
+fn parse_banner(s: &str) -> Result<(Field, Symmetry)> {
+    let words: Vec<String> = s.split_whitespace().map(|w| w.to_lowercase()).collect();
+    if words.len() != 5 || words[0] != "%%matrixmarket" || words[1] != "matrix" {
+        return Err(Error::parse(1, "missing %%MatrixMarket matrix banner"));
+    }
+    if words[2] != "coordinate" {
+        return Err(Error::parse(1, format!("unsupported format {:?}, expected coordinate", words[2])));
+    }
+    let field = match words[3].as_str() {
+        "pattern" => Field::Pattern,
+        "real" | "double" => Field::Real,
+        "integer" => Field::Integer,
+        f => return Err(Error::parse(1, format!("unsupported field {:?}", f))),
+    };
+    let symmetry = match words[4].as_str() {
+        "general" => Symmetry::General,
+        "symmetric" => Symmetry::Symmetric,
+        s => return Err(Error::parse(1, format!("unsupported symmetry {:?}", s))),
+    };
+    Ok((field, symmetry))
+}
+
+/*Read the Matrix Market file at path, handing f the line number, 0-based
+ids, and value field of each edge, including the mirror image of each
+off-diagonal entry of a symmetric file.  Fails on a pattern file if values
+are needed.*/
+fn read(
+    path: &str,
+    values: bool,
+    mut f: impl FnMut(u64, usize, usize, Option<&str>) -> Result<()>,
+) -> Result<MtxHeader> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let mut line = 0u64;
+    let mut next = || -> Result<Option<(u64, String)>> {
+        for l in lines.by_ref() {
+            line += 1;
+            let l = l?;
+            /*The banner is line 1; after it, skip comments and blank lines*/
+            if line == 1 || !(l.starts_with('%') || l.trim().is_empty()) {
+                return Ok(Some((line, l)));
+            }
+        }
+        Ok(None)
+    };
+
+    let (field, symmetry) = match next()? {
+        Some((_, l)) => parse_banner(&l)?,
+        None => return Err(Error::parse(1, "empty file")),
+    };
+    if values && field == Field::Pattern {
+        return Err(Error::parse(1, "pattern matrix has no values to use as weights"));
+    }
+
+    let (n, size) = next()?.ok_or_else(|| Error::parse(1, "missing size line"))?;
+    let dims: Vec<usize> = size
+        .split_whitespace()
+        .map(|w| w.parse::<usize>().ok())
+        .collect::<Option<_>>()
+        .filter(|d: &Vec<usize>| d.len() == 3)
+        .ok_or_else(|| Error::parse(n, format!("bad size line {:?}", size)))?;
+    let h = MtxHeader {
+        rows: dims[0],
+        cols: dims[1],
+        entries: dims[2],
+        field,
+        symmetry,
+    };
+
+    let want = if field == Field::Pattern { 2 } else { 3 };
+    let (mut seen, mut last) = (0, n);
+    while let Some((n, l)) = next()? {
+        last = n;
+        let words: Vec<&str> = l.split_whitespace().collect();
+        if words.len() != want {
+            return Err(Error::parse(n, format!("expected {} fields, found {}", want, words.len())));
+        }
+        let id = |i: usize, bound: usize| -> Result<usize> {
+            let x = words[i]
+                .parse::<usize>()
+                .map_err(|_| Error::parse(n, format!("bad vertex id {:?}", words[i])))?;
+            if x == 0 {
+                return Err(Error::parse(n, "vertex ids are 1-based"));
+            }
+            if x > bound {
+                return Err(Error::VertexOutOfRange { vtx: x - 1, v: bound });
+            }
+            Ok(x - 1)
+        };
+        let v0 = id(0, h.rows)?;
+        let v1 = id(1, h.cols)?;
+        let val = words.get(2).copied();
+        f(n, v0, v1, val)?;
+        if symmetry == Symmetry::Symmetric && v0 != v1 {
+            f(n, v1, v0, val)?;
+        }
+        seen += 1;
+    }
+    if seen != h.entries {
+        return Err(Error::parse(last, format!("expected {} entries, found {}", h.entries, seen)));
+    }
+
+    Ok(h)
+}
+
+/// Read the edges of the Matrix Market file at path, ignoring any values.
+/// Fails if the file cannot be read, is not a coordinate file of a
+/// supported field and symmetry, or does not have as many entries, all in
+/// range, as its size line says.
+pub fn read_el(path: &str) -> Result<(MtxHeader, Vec<(usize, usize)>)> {
+    let mut el = Vec::new();
+    let h = read(path, false, |_, v0, v1, _| {
+        el.push((v0, v1));
+        Ok(())
+    })?;
+    Ok((h, el))
+}
+
+/// Read the edges of the Matrix Market file at path along with their
+/// values as weights of type W.  Fails as read_el does, or if the file is
+/// a pattern file or has a value that does not parse as a W.
+pub fn read_wel<W: Weight>(path: &str) -> Result<(MtxHeader, WeightedEdgeList<W>)> {
+    let mut el = Vec::new();
+    let h = read(path, true, |n, v0, v1, val| {
+        let s = val.unwrap_or_default();
+        let w = W::parse_weight(s).ok_or_else(|| Error::parse(n, format!("bad weight {:?}", s)))?;
+        el.push((v0, v1, w));
+        Ok(())
+    })?;
+    Ok((h, el))
+}
@@ -0,0 +1,338 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  Placing the edges of an edge list into CSR order.  This is shared by the
+  in-memory CSR builds and the edge list to FastCSR converter, which differ
+  only in where the offsets and neighbs arrays live: a Vec or a mapped file.
+
+  A build is two steps.  count() works out every vertex's out-degree (and
+  whatever else the chosen way of placing needs later), then, once the
+  caller has laid out the offsets and allocated neighbs, place() puts every
+  edge in its slot.  The edges are given by edge(i) for i in 0..n, which is
+  None for an edge the build options drop, and, for a weighted build, the
+  8-byte word of each edge's weight by wword(i).
+*/
+
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::csr::BuildOptions;
+use crate::index::Idx;
+use crate::weight::Weight;
+
+/*Sources per propagation blocking block, so that a block's placement
+cursors (8 bytes each) fit in a 128KB L2*/
+const BLOCK_VERTICES: usize = 1 << 14;
+
+/*Per-block bins of one run of edges, (v0, v1, weight word)*/
+type Bins = Vec<Vec<(usize, usize, u64)>>;
+
+/*What count() learned that place() needs*/
+pub(crate) enum Plan {
+    /*Racing fetch_adds on one cursor per vertex*/
+    Atomic,
+    /*Each run of chunk edges' count per vertex*/
+    Runs { chunk: usize, cnt: Vec<Vec<usize>> },
+    /*Each run of edges binned by block of sources*/
+    Blocked(Vec<Bins>),
+}
+
+/*The edges the options turn the ne edges of an edge list into.  Edges
+ne..2*ne are the reverses of edges 0..ne when symmetrizing.  A self-loop is
+its own reverse, so it is only added once.  Returns how many edges there are
+and a function giving each one, along with the index of the input edge it
+came from.*/
+pub(crate) fn expand<'a>(
+    ne: usize,
+    opts: &'a BuildOptions,
+    edge: impl Fn(usize) -> (usize, usize) + Sync + 'a,
+) -> (usize, impl Fn(usize) -> Option<(usize, usize)> + Sync + 'a) {
+    let n = if opts.symmetrize { 2 * ne } else { ne };
+    let directed = move |i: usize| match i {
+        i if i < ne => match edge(i) {
+            (v0, v1) if v0 == v1 && opts.remove_self_loops => None,
+            e => Some(e),
+        },
+        i => match edge(i - ne) {
+            (v0, v1) if v0 == v1 => None,
+            (v0, v1) => Some((v1, v0)),
+        },
+    };
+    (n, directed)
+}
+
+/*Count up the number of neighbors that each vertex has, in the way the
+options ask edges to be placed*/
+pub(crate) fn count<E, F>(
+    numv: usize,
+    n: usize,
+    edge: &E,
+    wword: &F,
+    opts: &BuildOptions,
+) -> (Vec<usize>, Plan)
+where
+    E: Fn(usize) -> Option<(usize, usize)> + Sync,
+    F: Fn(usize) -> u64 + Sync,
+{
+    if opts.propagation_blocking {
+        /*Each of nruns threads bins its contiguous run of the edges by the
+        block of BLOCK_VERTICES sources it belongs to.  Then each block is
+        counted, and later placed, on one thread, reading the runs' bins in
+        order, so nothing is shared and neighbors keep edge list order.*/
+        let nblocks = numv.div_ceil(BLOCK_VERTICES);
+        let nruns = rayon::current_num_threads().clamp(1, n.max(1));
+        let run_len = n.div_ceil(nruns);
+        let bins: Vec<Bins> = (0..nruns)
+            .into_par_iter()
+            .map(|r| {
+                let mut bins = vec![Vec::new(); nblocks];
+                for i in r * run_len..((r + 1) * run_len).min(n) {
+                    if let Some((v0, v1)) = edge(i) {
+                        bins[v0 / BLOCK_VERTICES].push((v0, v1, wword(i)));
+                    }
+                }
+                bins
+            })
+            .collect();
+
+        let degs = (0..nblocks)
+            .into_par_iter()
+            .flat_map_iter(|b| {
+                let (lo, hi) = block(numv, b);
+                let mut cnt = vec![0; hi - lo];
+                for run in &bins {
+                    for &(v0, _, _) in &run[b] {
+                        cnt[v0 - lo] += 1;
+                    }
+                }
+                cnt
+            })
+            .collect();
+        return (degs, Plan::Blocked(bins));
+    }
+
+    if opts.deterministic {
+        /*The edges are split into runs and each run counts its own edges per
+        vertex, so it knows exactly where in each vertex's range its edges
+        go.  There are never more runs than edges per vertex, so the counts
+        take no more space than the edges.*/
+        let nruns = (n / numv.max(1)).clamp(1, rayon::current_num_threads());
+        let chunk = n.div_ceil(nruns);
+        let cnt: Vec<Vec<usize>> = (0..nruns)
+            .into_par_iter()
+            .map(|c| {
+                let mut cnt = vec![0; numv];
+                for (v0, _) in run(n, chunk, c).filter_map(edge) {
+                    cnt[v0] += 1;
+                }
+                cnt
+            })
+            .collect();
+        let degs = (0..numv)
+            .into_par_iter()
+            .map(|v| cnt.iter().map(|c| c[v]).sum())
+            .collect();
+        return (degs, Plan::Runs { chunk, cnt });
+    }
+
+    let mut ncnt = Vec::with_capacity(numv);
+    for _ in 0..numv {
+        ncnt.push(AtomicUsize::new(0));
+    }
+    (0..n).into_par_iter().for_each(|i| {
+        if let Some((v0, _)) = edge(i) {
+            ncnt[v0].fetch_add(1, Ordering::SeqCst);
+        }
+    });
+    let degs = ncnt.into_iter().map(|c| c.into_inner()).collect();
+    (degs, Plan::Atomic)
+}
+
+/*The vertices in propagation blocking block b*/
+fn block(numv: usize, b: usize) -> (usize, usize) {
+    (b * BLOCK_VERTICES, ((b + 1) * BLOCK_VERTICES).min(numv))
+}
+
+/*The edges in run c of runs of chunk edges*/
+fn run(n: usize, chunk: usize, c: usize) -> std::ops::Range<usize> {
+    c * chunk..((c + 1) * chunk).min(n)
+}
+
+/* CSR Structure e.g.,
+  |0,3,5,6,9|
+  |v2,v3,v5|v1,v9|v2|v3,v7,v8|x|
+*/
+/*vertex i's offset is vtx i-1's offset + i-1's neighbor count*/
+pub(crate) fn offsets_into<O: Idx>(degs: &[usize], offsets: &mut [O]) {
+    let mut off = 0;
+    for (o, d) in offsets.iter_mut().zip(degs.iter()) {
+        *o = O::from_usize(off);
+        off += d;
+    }
+}
+
+/*Populate the neighbor array (and the weight words alongside it, if wts is
+not empty) based on the counts*/
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn place<E, F, I, O>(
+    plan: Plan,
+    numv: usize,
+    n: usize,
+    edge: &E,
+    wword: &F,
+    offsets: &[O],
+    nbs: &mut [I],
+    wts: &mut [u64],
+) where
+    E: Fn(usize) -> Option<(usize, usize)> + Sync,
+    F: Fn(usize) -> u64 + Sync,
+    I: Idx,
+    O: Idx,
+{
+    let weighted = !wts.is_empty();
+    match plan {
+        Plan::Atomic => {
+            let work_offsets: Vec<AtomicUsize> =
+                offsets.iter().map(|o| AtomicUsize::new(o.index())).collect();
+            let (anbs, awts) = (I::atomic_slice(nbs), u64::atomic_slice(wts));
+            (0..n).into_par_iter().for_each(|i| {
+                if let Some((v0, v1)) = edge(i) {
+                    let cur_ind = work_offsets[v0].fetch_add(1, Ordering::SeqCst);
+                    I::store(&anbs[cur_ind], I::from_usize(v1));
+                    if weighted {
+                        u64::store(&awts[cur_ind], wword(i));
+                    }
+                }
+            });
+        }
+        Plan::Runs { chunk, mut cnt } => {
+            /*Turn each run's counts into the index its first edge to each
+            vertex goes to, then let each run fill in its slots*/
+            for (v, off) in offsets.iter().enumerate() {
+                let mut pos = off.index();
+                for c in cnt.iter_mut() {
+                    let k = c[v];
+                    c[v] = pos;
+                    pos += k;
+                }
+            }
+            let (anbs, awts) = (I::atomic_slice(nbs), u64::atomic_slice(wts));
+            cnt.into_par_iter().enumerate().for_each(|(c, mut pos)| {
+                for i in run(n, chunk, c) {
+                    if let Some((v0, v1)) = edge(i) {
+                        I::store(&anbs[pos[v0]], I::from_usize(v1));
+                        if weighted {
+                            u64::store(&awts[pos[v0]], wword(i));
+                        }
+                        pos[v0] += 1;
+                    }
+                }
+            });
+        }
+        Plan::Blocked(bins) => {
+            /*Each block's neighbors are contiguous, so carve neighbs and the
+            weights into one slice per block*/
+            let nblocks = numv.div_ceil(BLOCK_VERTICES);
+            let mut segs = Vec::with_capacity(nblocks);
+            let mut nrest: &mut [I] = nbs;
+            let mut wrest: &mut [u64] = wts;
+            for b in 0..nblocks {
+                let (lo, hi) = block(numv, b);
+                let len = match hi {
+                    hi if hi == numv => nrest.len(),
+                    hi => offsets[hi].index() - offsets[lo].index(),
+                };
+                let (nb, nr) = std::mem::take(&mut nrest).split_at_mut(len);
+                nrest = nr;
+                let wlen = if weighted { len } else { 0 };
+                let (wt, wr) = std::mem::take(&mut wrest).split_at_mut(wlen);
+                wrest = wr;
+                segs.push((nb, wt));
+            }
+
+            segs.into_par_iter().enumerate().for_each(|(b, (nb, wt))| {
+                let (lo, hi) = block(numv, b);
+                let base = offsets[lo].index();
+                let mut cur: Vec<usize> = offsets[lo..hi].iter().map(|o| o.index() - base).collect();
+                for run in &bins {
+                    for &(v0, v1, w) in &run[b] {
+                        let k = cur[v0 - lo];
+                        nb[k] = I::from_usize(v1);
+                        if weighted {
+                            wt[k] = w;
+                        }
+                        cur[v0 - lo] += 1;
+                    }
+                }
+            });
+        }
+    }
+}
+
+/*Sort each vertex's neighbors (degs[v] of them, one after another in nbs),
+carrying their weights in wts along if there are any, in parallel.  With
+dedup, also drop repeated neighbors, keeping the smallest weight of each
+in Weight::total_cmp order; a vertex's kept neighbors are moved to the
+front of its range and degs[v] is set to how many there are.*/
+pub(crate) fn sort_segments<I: Idx, W: Weight>(
+    degs: &mut [usize],
+    nbs: &mut [I],
+    wts: &mut [W],
+    dedup: bool,
+) {
+    let weighted = !wts.is_empty();
+
+    /*Carve the neighbs and weights arrays into one slice per vertex*/
+    let mut nrest: &mut [I] = nbs;
+    let mut wrest: &mut [W] = wts;
+    let mut segs = Vec::with_capacity(degs.len());
+    for deg in degs.iter() {
+        let (nb, nr) = std::mem::take(&mut nrest).split_at_mut(*deg);
+        nrest = nr;
+        let wlen = if weighted { *deg } else { 0 };
+        let (wt, wr) = std::mem::take(&mut wrest).split_at_mut(wlen);
+        wrest = wr;
+        segs.push((nb, wt));
+    }
+
+    degs.par_iter_mut()
+        .zip(segs.into_par_iter())
+        .for_each(|(deg, (nb, wt))| match weighted {
+            false => {
+                nb.sort_unstable();
+                if dedup {
+                    let mut uniq = nb.to_vec();
+                    uniq.dedup();
+                    nb[..uniq.len()].copy_from_slice(&uniq);
+                    *deg = uniq.len();
+                }
+            }
+            true => {
+                let mut pairs: Vec<(I, W)> =
+                    nb.iter().cloned().zip(wt.iter().cloned()).collect();
+                pairs.sort_unstable_by(|a, b| {
+                    a.0.cmp(&b.0)
+                        .then(a.1.total_cmp(&b.1))
+                });
+                if dedup {
+                    pairs.dedup_by_key(|p| p.0);
+                }
+                for (i, (n, w)) in pairs.iter().enumerate() {
+                    nb[i] = *n;
+                    wt[i] = *w;
+                }
+                *deg = pairs.len();
+            }
+        });
+}
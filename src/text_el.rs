@@ -0,0 +1,202 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  Text edge lists in the many dialects graphs are published in: SNAP's tab
+  separated pairs under # comments, space separated lists, csv with a header
+  row, 1-based ids, a weight in some column.  The file is mapped and split
+  at line boundaries into chunks that are parsed in parallel, so reading a
+  multi-gigabyte list is bound by the disk, not by one core.
+*/
+
+use memmap2::Mmap;
+use rayon::prelude::*;
+use std::fs::File;
+use std::path::Path;
+
+use crate::csr::WeightedEdgeList;
+use crate::error::{Error, FormatError, Result};
+use crate::weight::Weight;
+
+/*Chunks are at least this many bytes, so small files are not split up*/
+const MIN_CHUNK: usize = 1 << 20;
+
+/// How a text edge list is laid out.  Each line holds an edge's source and
+/// target ids in its first two fields, and optionally a weight; any other
+/// fields are ignored.  The default reads SNAP files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextFormat {
+    /// The character between fields, or None to split on any run of
+    /// spaces and tabs.  Fields are trimmed either way.
+    pub delimiter: Option<char>,
+    /// Lines starting with this, after any leading whitespace, are skipped
+    pub comment: Option<String>,
+    /// The number of lines at the top of the file to skip, e.g., 1 for a
+    /// header row
+    pub skip_lines: usize,
+    /// The field (counting from 0) holding each edge's weight when reading
+    /// a weighted list, the third if None
+    pub weight_column: Option<usize>,
+    /// Ids in the file count from 1, and are shifted down to count from 0
+    pub one_based: bool,
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        TextFormat {
+            delimiter: None,
+            comment: Some("#".to_string()),
+            skip_lines: 0,
+            weight_column: None,
+            one_based: false,
+        }
+    }
+}
+
+impl TextFormat {
+    /// SNAP's format: whitespace separated ids under # comments
+    pub fn snap() -> TextFormat {
+        TextFormat::default()
+    }
+
+    /// Comma separated fields with no comments, as el_from_file reads
+    pub fn csv() -> TextFormat {
+        TextFormat {
+            delimiter: Some(','),
+            comment: None,
+            ..Default::default()
+        }
+    }
+}
+
+/*Split the bytes after the header into chunks ending at line boundaries*/
+fn chunks(bytes: &[u8]) -> Vec<(usize, usize)> {
+    let size = (bytes.len() / (4 * rayon::current_num_threads())).max(MIN_CHUNK);
+    let mut cuts = vec![0];
+    let mut at = size;
+    while at < bytes.len() {
+        match bytes[at..].iter().position(|b| *b == b'\n') {
+            Some(nl) => cuts.push(at + nl + 1),
+            None => break,
+        }
+        at = cuts[cuts.len() - 1] + size;
+    }
+    if *cuts.last().unwrap() < bytes.len() {
+        cuts.push(bytes.len());
+    }
+    cuts.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+/*Parse the text edge list at path in parallel, handing parse_edge the
+fields of each edge line.  Errors carry the line of the file they are on.*/
+pub(crate) fn read<T: Send>(
+    path: &Path,
+    fmt: &TextFormat,
+    parse_edge: impl Fn(&[&str]) -> std::result::Result<T, String> + Sync,
+) -> Result<Vec<T>> {
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file)? };
+
+    /*Skip the header lines*/
+    let mut start = 0;
+    for _ in 0..fmt.skip_lines {
+        match mmap[start..].iter().position(|b| *b == b'\n') {
+            Some(nl) => start += nl + 1,
+            None => start = mmap.len(),
+        }
+    }
+    let body = &mmap[start..];
+
+    let lines_before = |at: usize| body[..at].iter().filter(|b| **b == b'\n').count() as u64;
+    let parsed: Result<Vec<Vec<T>>> = chunks(body)
+        .into_par_iter()
+        .map(|(lo, hi)| {
+            let mut out = Vec::new();
+            let mut fields = Vec::new();
+            for (i, l) in body[lo..hi].split(|b| *b == b'\n').enumerate() {
+                let fail = |msg: String| {
+                    let line = fmt.skip_lines as u64 + lines_before(lo) + i as u64 + 1;
+                    Error::parse(line, msg)
+                };
+                let l = std::str::from_utf8(l).map_err(|_| fail("line is not UTF-8".to_string()))?;
+                let l = l.trim();
+                if l.is_empty() || fmt.comment.as_ref().is_some_and(|c| l.starts_with(c.as_str())) {
+                    continue;
+                }
+                fields.clear();
+                match fmt.delimiter {
+                    Some(d) => fields.extend(l.split(d).map(|f| f.trim())),
+                    None => fields.extend(l.split_whitespace()),
+                }
+                out.push(parse_edge(&fields).map_err(fail)?);
+            }
+            Ok(out)
+        })
+        .collect();
+
+    let parsed = parsed?;
+    let mut el = Vec::with_capacity(parsed.iter().map(|c| c.len()).sum());
+    for c in parsed {
+        el.extend(c);
+    }
+    Ok(el)
+}
+
+/*Parse field i of an edge line as a 0-based vertex id*/
+fn vertex(fields: &[&str], i: usize, one_based: bool) -> std::result::Result<usize, String> {
+    let s = fields.get(i).ok_or_else(|| "missing vertex id".to_string())?;
+    let x = s
+        .parse::<usize>()
+        .map_err(|_| format!("bad vertex id {:?}", s))?;
+    match (one_based, x) {
+        (true, 0) => Err("vertex ids are 1-based".to_string()),
+        (true, x) => Ok(x - 1),
+        (false, x) => Ok(x),
+    }
+}
+
+/*One more than the largest id in the list*/
+fn numv(maxv: Option<usize>, e: usize) -> Result<usize> {
+    match maxv {
+        Some(vtx) => vtx
+            .checked_add(1)
+            .ok_or(Error::Format(FormatError::TooLarge { v: vtx, e })),
+        None => Ok(0),
+    }
+}
+
+/// Read the text edge list at path, laid out as fmt says, ignoring any
+/// weights.  Returns the number of vertices seen, one more than the
+/// largest id, and the edge list.  Fails if the file cannot be read or an
+/// edge line does not start with two vertex ids.
+pub fn read_el<Q: AsRef<Path>>(path: Q, fmt: &TextFormat) -> Result<(usize, Vec<(usize, usize)>)> {
+    let el = read(path.as_ref(), fmt, |f| {
+        Ok((vertex(f, 0, fmt.one_based)?, vertex(f, 1, fmt.one_based)?))
+    })?;
+    let maxv = el.par_iter().map(|(v0, v1)| *v0.max(v1)).max();
+    Ok((numv(maxv, el.len())?, el))
+}
+
+/// Read the weighted text edge list at path, laid out as fmt says.  Fails
+/// as read_el does, or if an edge line's weight is missing or does not
+/// parse as a W.
+pub fn read_wel<W: Weight, Q: AsRef<Path>>(path: Q, fmt: &TextFormat) -> Result<(usize, WeightedEdgeList<W>)> {
+    let col = fmt.weight_column.unwrap_or(2);
+    let el = read(path.as_ref(), fmt, |f| {
+        let s = f.get(col).ok_or_else(|| "missing weight".to_string())?;
+        let w = W::parse_weight(s).ok_or_else(|| format!("bad weight {:?}", s))?;
+        Ok((vertex(f, 0, fmt.one_based)?, vertex(f, 1, fmt.one_based)?, w))
+    })?;
+    let maxv = el.par_iter().map(|(v0, v1, _)| *v0.max(v1)).max();
+    Ok((numv(maxv, el.len())?, el))
+}
@@ -0,0 +1,85 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*
+  The view of a graph that traversals (bfs.rs, dfs.rs) need: its number of
+  vertices and each vertex's neighbors as a slice, so one traversal serves
+  both an in-memory CSR and a mapped FastCSR.  Graphs that keep their
+  in-edges (CSR::with_in_edges, or a FastCSR written from one) also give
+  each vertex's in-neighbors, for bottom-up steps.
+*/
+
+use crate::csr::CSR;
+use crate::fast_csr::FastCSR;
+use crate::index::Idx;
+use crate::weight::Weight;
+use crate::PropTrait;
+
+/// A graph whose vertices 0..num_v() and their out-neighbors can be read,
+/// from any thread
+pub trait Adjacency: Sync {
+    type Id: Idx;
+
+    fn num_v(&self) -> usize;
+
+    /// The vertices vertex v has an edge to
+    fn out_neighbors(&self, v: usize) -> &[Self::Id];
+
+    /// True if in_neighbors can be called
+    fn has_in_edges(&self) -> bool;
+
+    /// The vertices with an edge to vertex v.  Panics if the graph does not
+    /// keep its in-edges.
+    fn in_neighbors(&self, v: usize) -> &[Self::Id];
+}
+
+impl<P: PropTrait, W: Weight, I: Idx, O: Idx> Adjacency for CSR<P, W, I, O> {
+    type Id = I;
+
+    fn num_v(&self) -> usize {
+        self.get_v()
+    }
+
+    fn out_neighbors(&self, v: usize) -> &[I] {
+        let (st, en) = self.vtx_offset_range(v);
+        &self.get_neighbs()[st..en]
+    }
+
+    fn has_in_edges(&self) -> bool {
+        CSR::has_in_edges(self)
+    }
+
+    fn in_neighbors(&self, v: usize) -> &[I] {
+        CSR::in_neighbors(self, v)
+    }
+}
+
+impl<I: Idx, O: Idx> Adjacency for FastCSR<I, O> {
+    type Id = I;
+
+    fn num_v(&self) -> usize {
+        self.getv()
+    }
+
+    fn out_neighbors(&self, v: usize) -> &[I] {
+        self.neighbors(v)
+    }
+
+    fn has_in_edges(&self) -> bool {
+        FastCSR::has_in_edges(self)
+    }
+
+    fn in_neighbors(&self, v: usize) -> &[I] {
+        FastCSR::in_neighbors(self, v)
+    }
+}
@@ -11,6 +11,12 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+#![allow(
+    clippy::match_ref_pats,
+    clippy::needless_range_loop,
+    clippy::needless_return,
+)]
+
 use crate::VtxTrait;
 
 #[derive(Debug)]
@@ -0,0 +1,135 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use byte_slice_cast::{FromByteSlice, ToByteSlice};
+use std::cmp::Ordering;
+
+/*Weight kinds recorded in the flags of a FastCSR file*/
+pub const KIND_F64: u64 = 1;
+pub const KIND_F32: u64 = 2;
+pub const KIND_U64: u64 = 3;
+pub const KIND_U32: u64 = 4;
+pub const KIND_I64: u64 = 5;
+pub const KIND_I32: u64 = 6;
+
+/// Width in bytes of a weight of the given kind, or None for an unknown kind
+pub fn kind_width(kind: u64) -> Option<usize> {
+    match kind {
+        KIND_F64 | KIND_U64 | KIND_I64 => Some(8),
+        KIND_F32 | KIND_U32 | KIND_I32 => Some(4),
+        _ => None,
+    }
+}
+
+/// An edge weight.  Weights are stored in plain arrays parallel to the
+/// neighbs array, so they have to be plain numbers that can be cast to and
+/// from bytes.
+pub trait Weight:
+    Copy
+    + Default
+    + PartialOrd
+    + Send
+    + Sync
+    + std::fmt::Debug
+    + std::fmt::Display
+    + FromByteSlice
+    + ToByteSlice
+    + 'static
+{
+    /// Tag recorded in FastCSR files so a loader can check the weight type
+    const KIND: u64;
+
+    /// Decode a weight from the third 8-byte word of a binary edge triple
+    fn from_word(w: u64) -> Self;
+
+    /// Encode a weight as an 8-byte word, the inverse of from_word
+    fn to_word(self) -> u64;
+
+    /// Parse a weight from a field of a text edge list
+    fn parse_weight(s: &str) -> Option<Self>;
+
+    /// Compare two weights in a total order: numeric order for integers,
+    /// and for floats the IEEE 754 totalOrder, which puts NaNs after every
+    /// number (or before, if their sign bit is set)
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+macro_rules! int_weight {
+    ($t:ty, $kind:expr) => {
+        impl Weight for $t {
+            const KIND: u64 = $kind;
+
+            fn from_word(w: u64) -> $t {
+                w as $t
+            }
+
+            fn to_word(self) -> u64 {
+                self as u64
+            }
+
+            fn parse_weight(s: &str) -> Option<$t> {
+                s.trim().parse::<$t>().ok()
+            }
+
+            fn total_cmp(&self, other: &$t) -> Ordering {
+                self.cmp(other)
+            }
+        }
+    };
+}
+
+int_weight!(u64, KIND_U64);
+int_weight!(u32, KIND_U32);
+int_weight!(i64, KIND_I64);
+int_weight!(i32, KIND_I32);
+
+impl Weight for f64 {
+    const KIND: u64 = KIND_F64;
+
+    fn from_word(w: u64) -> f64 {
+        f64::from_bits(w)
+    }
+
+    fn to_word(self) -> u64 {
+        self.to_bits()
+    }
+
+    fn parse_weight(s: &str) -> Option<f64> {
+        s.trim().parse::<f64>().ok()
+    }
+
+    fn total_cmp(&self, other: &f64) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+}
+
+impl Weight for f32 {
+    const KIND: u64 = KIND_F32;
+
+    /*A 4-byte float is kept in the low half of the word*/
+    fn from_word(w: u64) -> f32 {
+        f32::from_bits(w as u32)
+    }
+
+    fn to_word(self) -> u64 {
+        self.to_bits() as u64
+    }
+
+    fn parse_weight(s: &str) -> Option<f32> {
+        s.trim().parse::<f32>().ok()
+    }
+
+    fn total_cmp(&self, other: &f32) -> Ordering {
+        f32::total_cmp(self, other)
+    }
+}
@@ -0,0 +1,127 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::bfs::{BfsTree, Direction, DirectionOptions, UNREACHED};
+use raphy::csr::CSR;
+use raphy::fast_csr::{FastCSR, Validation};
+use std::collections::VecDeque;
+use std::fs;
+
+/*Depths by a sequential queue-based search*/
+fn depths(csr: &CSR, start: usize) -> Vec<usize> {
+    let mut depth = vec![UNREACHED; csr.get_v()];
+    let mut q = VecDeque::new();
+    depth[start] = 0;
+    q.push_back(start);
+    while let Some(v) = q.pop_front() {
+        let (st, en) = csr.vtx_offset_range(v);
+        for n in &csr.get_neighbs()[st..en] {
+            if depth[*n] == UNREACHED {
+                depth[*n] = depth[v] + 1;
+                q.push_back(*n);
+            }
+        }
+    }
+    depth
+}
+
+/*Every reached vertex but the start has a parent one level up with an
+edge to it*/
+fn check_parents(csr: &CSR, t: &BfsTree, start: usize) {
+    assert_eq!(t.parent[start], start);
+    for v in (0..csr.get_v()).filter(|v| *v != start) {
+        match t.parent[v] {
+            UNREACHED => assert!(!t.reached(v)),
+            p => {
+                assert_eq!(t.depth[p] + 1, t.depth[v]);
+                assert!(csr.has_edge(p, v));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_par_bfs() {
+    const NUMV: usize = 5000;
+    let csr = CSR::new(NUMV, CSR::random_el(NUMV, 4));
+
+    for start in [0, 17, NUMV - 1].iter() {
+        let t = csr.par_bfs(*start);
+        assert_eq!(t.depth, depths(&csr, *start));
+        check_parents(&csr, &t, *start);
+        assert_eq!(t.tree_edges().len(), (0..NUMV).filter(|v| t.reached(*v)).count() - 1);
+    }
+
+    /*The same search over the graph written as a FastCSR*/
+    let path = std::env::temp_dir().join("raphy_bfs.csr");
+    csr.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let fcsr = FastCSR::open(&path).unwrap();
+    let any = FastCSR::open_any(&path, Validation::Parallel).unwrap();
+    let _ = fs::remove_file(&path);
+    let t = fcsr.par_bfs(17);
+    assert_eq!(t.depth, depths(&csr, 17));
+    check_parents(&csr, &t, 17);
+    assert_eq!(any.par_bfs(17).depth, t.depth);
+}
+
+#[test]
+fn test_par_bfs_unreached() {
+    /*Two components and a vertex with no edges*/
+    let csr = CSR::new(7, vec![(0, 1), (1, 2), (2, 0), (2, 3), (4, 5), (5, 4)]);
+    let t = csr.par_bfs(1);
+    assert_eq!(t.depth, vec![2, 0, 1, 2, UNREACHED, UNREACHED, UNREACHED]);
+    assert_eq!(t.parent, vec![2, 1, 1, 2, UNREACHED, UNREACHED, UNREACHED]);
+    let mut edges = t.tree_edges();
+    edges.sort_unstable();
+    assert_eq!(edges, vec![(1, 2), (2, 0), (2, 3)]);
+
+    let t = csr.par_bfs(6);
+    assert_eq!(t.depth.iter().filter(|d| **d != UNREACHED).count(), 1);
+}
+
+#[test]
+fn test_direction_optimizing_bfs() {
+    const NUMV: usize = 20000;
+    let csr = CSR::new(NUMV, CSR::random_el(NUMV, 16)).with_in_edges();
+    /*Start from the vertex with the most edges, to reach most of the graph*/
+    let start = (0..NUMV).max_by_key(|v| csr.out_degree(*v)).unwrap();
+
+    let (t, dirs) = csr.par_do_bfs(start, &DirectionOptions::default());
+    assert_eq!(t.depth, depths(&csr, start));
+    check_parents(&csr, &t, start);
+    let max = t.depth.iter().filter(|d| **d != UNREACHED).max().unwrap();
+    assert_eq!(dirs.len(), *max);
+    assert_eq!(dirs[0], Direction::TopDown);
+    assert!(dirs.contains(&Direction::BottomUp));
+
+    /*An alpha this large goes bottom-up at once, a beta this large never
+    comes back*/
+    let always_up = DirectionOptions {
+        alpha: usize::MAX,
+        beta: usize::MAX,
+    };
+    let (t, dirs) = csr.par_do_bfs(start, &always_up);
+    assert_eq!(t.depth, depths(&csr, start));
+    check_parents(&csr, &t, start);
+    assert!(dirs.iter().all(|d| *d == Direction::BottomUp));
+
+    /*The in-edges a FastCSR file keeps serve the same way*/
+    let path = std::env::temp_dir().join("raphy_do_bfs.csr");
+    csr.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let fcsr = FastCSR::open(&path).unwrap();
+    let _ = fs::remove_file(&path);
+    let (t, _) = fcsr.par_do_bfs(start, &DirectionOptions::default());
+    assert_eq!(t.depth, depths(&csr, start));
+    check_parents(&csr, &t, start);
+}
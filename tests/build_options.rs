@@ -0,0 +1,153 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::{BuildOptions, CSR};
+use std::collections::BTreeSet;
+use std::fs;
+
+fn neighbors(csr: &CSR, v: usize) -> &[usize] {
+    let (st, en) = csr.vtx_offset_range(v);
+    &csr.get_neighbs()[st..en]
+}
+
+#[test]
+fn test_build_options() {
+    let el = vec![(0, 1), (0, 1), (1, 1), (2, 0), (0, 2), (3, 1), (1, 0)];
+
+    let csr = CSR::new(4, el.clone());
+    assert_eq!(csr.get_e(), el.len());
+    assert!(csr.has_edge(1, 1));
+
+    let sorted = CSR::new_with(
+        4,
+        el.clone(),
+        &BuildOptions {
+            sort: true,
+            ..Default::default()
+        },
+    );
+    assert!(sorted.is_sorted());
+    assert_eq!(neighbors(&sorted, 0), &[1, 1, 2]);
+
+    let simple = CSR::new_with(
+        4,
+        el.clone(),
+        &BuildOptions {
+            remove_self_loops: true,
+            dedup: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(simple.get_e(), 5);
+    assert_eq!(neighbors(&simple, 0), &[1, 2]);
+    assert_eq!(neighbors(&simple, 1), &[0]);
+    assert!(!simple.has_edge(1, 1));
+
+    /*Symmetrizing adds a self-loop only once*/
+    let undirected = CSR::new_with(
+        4,
+        el,
+        &BuildOptions {
+            symmetrize: true,
+            dedup: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(neighbors(&undirected, 0), &[1, 2]);
+    assert_eq!(neighbors(&undirected, 1), &[0, 1, 3]);
+    assert_eq!(neighbors(&undirected, 2), &[0]);
+    assert_eq!(neighbors(&undirected, 3), &[1]);
+    assert!(undirected.has_edge(3, 1) && undirected.has_edge(1, 3));
+}
+
+#[test]
+fn test_build_options_random() {
+    const NUMV: usize = 300;
+    let el = CSR::random_el(NUMV, 20);
+    let opts = BuildOptions {
+        symmetrize: true,
+        remove_self_loops: true,
+        dedup: true,
+        sort: true,
+        deterministic: false,
+        propagation_blocking: false,
+    };
+    let csr = CSR::new_with(NUMV, el.clone(), &opts);
+
+    let mut expect = vec![BTreeSet::new(); NUMV];
+    for &(v0, v1) in el.iter().filter(|(v0, v1)| v0 != v1) {
+        expect[v0].insert(v1);
+        expect[v1].insert(v0);
+    }
+    for (v, nbs) in expect.iter().enumerate() {
+        assert_eq!(neighbors(&csr, v), &nbs.iter().cloned().collect::<Vec<_>>()[..]);
+    }
+    assert_eq!(csr.get_e(), expect.iter().map(|s| s.len()).sum::<usize>());
+
+    /*The binary edge list loader takes the same options*/
+    let path = std::env::temp_dir().join("raphy_build_options.el");
+    let bytes: Vec<u8> = el
+        .iter()
+        .flat_map(|&(v0, v1)| [v0 as u64, v1 as u64].to_vec())
+        .flat_map(|w| w.to_ne_bytes().to_vec())
+        .collect();
+    fs::write(&path, &bytes).unwrap();
+    let mm = CSR::new_from_el_mmap_with(NUMV, path.to_str().unwrap().to_string(), &opts).unwrap();
+    let _ = fs::remove_file(&path);
+    assert_eq!(mm.get_offsets(), csr.get_offsets());
+    assert_eq!(mm.get_neighbs(), csr.get_neighbs());
+}
+
+#[test]
+fn test_build_options_weighted() {
+    let el = vec![(0, 1, 5u32), (0, 1, 2), (1, 0, 9), (2, 2, 4), (0, 1, 3)];
+    let csr = CSR::new_weighted_with(
+        3,
+        el,
+        &BuildOptions {
+            symmetrize: true,
+            dedup: true,
+            ..Default::default()
+        },
+    );
+
+    /*Repeated edges keep their smallest weight, reverses keep theirs*/
+    assert_eq!(csr.weighted_neighbors(0), (&[1usize][..], &[2u32][..]));
+    assert_eq!(csr.weighted_neighbors(1), (&[0usize][..], &[2u32][..]));
+    assert_eq!(csr.weighted_neighbors(2), (&[2usize][..], &[4u32][..]));
+    assert_eq!(csr.get_e(), 3);
+}
+
+#[test]
+fn test_build_options_nan_weights() {
+    /*NaN weights sort after every number, so dedup keeps a number when
+    there is one, and a NaN only when every copy is NaN*/
+    let el = vec![
+        (0, 1, f64::NAN),
+        (0, 1, 2.5),
+        (0, 1, f64::NAN),
+        (0, 1, -1.0),
+        (1, 0, f64::NAN),
+        (1, 0, f64::NAN),
+    ];
+    let opts = BuildOptions {
+        dedup: true,
+        ..Default::default()
+    };
+    let csr = CSR::<f64, f64>::new_weighted_with(2, el, &opts);
+    assert_eq!(csr.weighted_neighbors(0), (&[1usize][..], &[-1.0][..]));
+    let (nbs, wts) = csr.weighted_neighbors(1);
+    assert_eq!(nbs, &[0usize][..]);
+    assert!(wts.len() == 1 && wts[0].is_nan());
+}
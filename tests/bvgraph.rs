@@ -0,0 +1,143 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::bvgraph::{BvGraph, BvOptions};
+use raphy::compressed::CompressedFastCSR;
+use raphy::csr::CSR;
+use raphy::error::FormatError;
+use raphy::fast_csr::{FastCSR, Validation};
+use raphy::Error;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn test_bvgraph_roundtrip() {
+    const NUMV: usize = 3000;
+    /*Runs of vertices share most of a list of hubs, and have runs of
+    consecutive neighbors, as in a web crawl, plus repeats and self-loops*/
+    let mut el = CSR::random_el(NUMV, 3);
+    for v in 0..NUMV {
+        let base = (v / 16) * 16;
+        el.extend((0..12).filter(|i| (v + i) % 5 != 0).map(|i| (v, (base * 7 + i * 3) % NUMV)));
+        el.extend((1..6).map(|i| (v, (v + i) % NUMV)));
+    }
+    el.extend((0..NUMV).step_by(9).map(|v| (v, v)));
+    el.extend((0..NUMV).step_by(9).map(|v| (v, v)));
+    let csr = CSR::new(NUMV, el);
+
+    let dir = std::env::temp_dir();
+    let plain = dir.join("raphy_bvgraph_plain.csr");
+    let packed = dir.join("raphy_bvgraph_packed.csr");
+    let bv = dir.join("raphy_bvgraph.bv");
+    csr.write_fastcsr(plain.to_str().unwrap().to_string()).unwrap();
+    csr.write_compressed_fastcsr(packed.to_str().unwrap().to_string()).unwrap();
+
+    let g = BvGraph::from_csr(&csr, &bv, &BvOptions::default()).unwrap();
+    let cfcsr = CompressedFastCSR::open(&packed).unwrap();
+    assert!(fs::metadata(&bv).unwrap().len() < fs::metadata(&packed).unwrap().len());
+    assert!(g.neighbs_len() < cfcsr.neighbs_len());
+
+    let g = BvGraph::open(&bv).unwrap();
+    let mut edges = 0;
+    for v in 0..NUMV {
+        let (st, en) = csr.vtx_offset_range(v);
+        let mut want = csr.get_neighbs()[st..en].to_vec();
+        want.sort_unstable();
+        want.dedup();
+        assert_eq!(g.neighbors(v), want);
+        assert_eq!(g.out_degree(v), want.len());
+        edges += want.len();
+    }
+    assert_eq!((g.getv(), g.gete()), (NUMV, edges));
+
+    let seen = AtomicUsize::new(0);
+    g.read_only_scan(|v0, v1| {
+        assert!(csr.has_edge(v0, v1));
+        seen.fetch_add(1, Ordering::Relaxed);
+    });
+    assert_eq!(seen.into_inner(), edges);
+
+    let mut largest = vec![0usize; NUMV];
+    g.neighbor_scan_prop(|v, nbs| nbs.last().copied().unwrap_or(v), &mut largest);
+    for (v, l) in largest.iter().enumerate() {
+        assert_eq!(*l, g.neighbors(v).last().copied().unwrap_or(v));
+    }
+
+    /*A FastCSR compresses to the same file, and turning copying off only
+    makes it bigger*/
+    let fcsr = FastCSR::open(&plain).unwrap();
+    let again = dir.join("raphy_bvgraph_again.bv");
+    BvGraph::from_fastcsr(&fcsr, &again, &BvOptions::default()).unwrap();
+    assert_eq!(fs::read(&again).unwrap(), fs::read(&bv).unwrap());
+    let opts = BvOptions {
+        max_ref_chain: 0,
+        ..Default::default()
+    };
+    let flat = BvGraph::from_fastcsr(&fcsr, &again, &opts).unwrap();
+    assert!(flat.neighbs_len() > g.neighbs_len());
+    assert!((0..NUMV).all(|v| flat.neighbors(v) == g.neighbors(v)));
+
+    for p in [&plain, &packed, &bv, &again].iter() {
+        let _ = fs::remove_file(p);
+    }
+}
+
+#[test]
+fn test_bvgraph_errors() {
+    let csr = CSR::new(4, vec![(0, 1), (0, 3), (1, 2), (3, 0)]);
+    let dir = std::env::temp_dir();
+    let packed = dir.join("raphy_bvgraph_err_packed.csr");
+    let bv = dir.join("raphy_bvgraph_err.bv");
+    csr.write_compressed_fastcsr(packed.to_str().unwrap().to_string()).unwrap();
+    BvGraph::from_csr(&csr, &bv, &BvOptions::default()).unwrap();
+
+    /*The two compressed loaders refuse each other's files*/
+    match BvGraph::open(&packed) {
+        Err(Error::Format(FormatError::References(false))) => (),
+        _ => panic!("expected References(false)"),
+    }
+    match CompressedFastCSR::open(&bv) {
+        Err(Error::Format(FormatError::References(true))) => (),
+        _ => panic!("expected References(true)"),
+    }
+    match FastCSR::open(&bv) {
+        Err(Error::Format(FormatError::Compressed(true))) => (),
+        _ => panic!("expected Compressed(true)"),
+    }
+
+    /*Vertex 3's list is its last bytes: |1|0|0|zigzag(0 - 3)|.  Make it
+    refer to a list past the start of the graph.*/
+    let mut bytes = fs::read(&bv).unwrap();
+    let n = bytes.len();
+    assert_eq!(bytes[n - 4..], [1, 0, 0, 5]);
+    bytes[n - 3] = 9;
+    fs::write(&bv, &bytes).unwrap();
+    assert!(BvGraph::open_with(&bv, Validation::Header).is_ok());
+    match BvGraph::open_with(&bv, Validation::Sequential) {
+        Err(Error::Format(FormatError::BadEncoding { vtx: 3 })) => (),
+        _ => panic!("expected BadEncoding"),
+    }
+
+    /*Point it at a neighbor past the end of the graph*/
+    bytes[n - 3] = 0;
+    bytes[n - 1] = 40;
+    fs::write(&bv, &bytes).unwrap();
+    match BvGraph::open_with(&bv, Validation::Parallel) {
+        Err(Error::Format(FormatError::NeighborOutOfRange { vtx: 3, .. })) => (),
+        _ => panic!("expected NeighborOutOfRange"),
+    }
+
+    let _ = fs::remove_file(&packed);
+    let _ = fs::remove_file(&bv);
+}
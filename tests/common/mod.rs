@@ -0,0 +1,47 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+/*Helpers shared by the integration tests.  Each test crate uses only some
+of them.*/
+#![allow(dead_code)]
+
+use raphy::csr::BuildOptions;
+use std::fs;
+use std::path::Path;
+
+/*Build options that sort each vertex's neighbors, so tests can compare
+neighbor lists*/
+pub fn sorted() -> BuildOptions {
+    BuildOptions {
+        sort: true,
+        ..Default::default()
+    }
+}
+
+/*Write text to a file named name in the temporary directory, returning its
+path*/
+pub fn write_tmp(name: &str, text: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    fs::write(&path, text).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+/*Write el to path as a binary edge list of native-endian 64-bit ids*/
+pub fn write_el(path: &Path, el: &[(usize, usize)]) {
+    let bytes: Vec<u8> = el
+        .iter()
+        .flat_map(|&(v0, v1)| [v0 as u64, v1 as u64].to_vec())
+        .flat_map(|w| w.to_ne_bytes().to_vec())
+        .collect();
+    fs::write(path, &bytes).unwrap();
+}
@@ -0,0 +1,102 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::{BuildOptions, CompactCSR, CSR};
+use raphy::error::FormatError;
+use raphy::fast_csr::{AnyFastCSR, FastCSR, Validation};
+use raphy::format::Header;
+use raphy::index;
+use raphy::Error;
+use std::fs;
+
+#[test]
+fn test_compact_csr() {
+    const NUMV: usize = 500;
+    let el = CSR::random_el(NUMV, 12);
+    let opts = BuildOptions {
+        sort: true,
+        ..Default::default()
+    };
+    let csr = CSR::new_with(NUMV, el.clone(), &opts);
+    let compact = CompactCSR::new_compact(NUMV, el, &opts).unwrap();
+
+    assert_eq!(compact.get_e(), csr.get_e());
+    for v in 0..NUMV {
+        let (st, en) = csr.vtx_offset_range(v);
+        let wide: Vec<u32> = csr.get_neighbs()[st..en].iter().map(|n| *n as u32).collect();
+        let (st, en) = compact.vtx_offset_range(v);
+        assert_eq!(&compact.get_neighbs()[st..en], &wide[..]);
+        assert!(wide.iter().all(|n| compact.has_edge(v, *n as usize)));
+    }
+
+    /*Converting carries the in-edges along, and converting back gives the
+    same graph*/
+    let narrowed = CSR::new_with(3, vec![(0, 1), (2, 1)], &opts)
+        .with_in_edges()
+        .with_index::<u32, u32>()
+        .unwrap();
+    assert_eq!(narrowed.in_neighbors(1), &[0u32, 2][..]);
+    let back = narrowed.with_index::<usize, usize>().unwrap();
+    assert_eq!(back.get_neighbs(), &[1, 1][..]);
+    assert_eq!(back.get_offsets(), &vec![0, 1, 1]);
+
+    assert!(index::fits::<u32, u32>(1 << 32, (1 << 32) - 1));
+    assert!(!index::fits::<u32, u32>((1 << 32) + 1, 0));
+    assert!(!index::fits::<u32, u32>(0, 1 << 32));
+    assert!(index::fits::<u32, usize>(0, 1 << 32));
+}
+
+#[test]
+fn test_compact_fastcsr() {
+    const NUMV: usize = 300;
+    let csr = CSR::new(NUMV, CSR::random_el(NUMV, 10)).with_in_edges();
+    let t = csr.transpose();
+
+    let path = std::env::temp_dir().join("raphy_compact.csr");
+    let p = path.to_str().unwrap().to_string();
+    t.with_index::<u32, u32>().unwrap().write_fastcsr(p.clone()).unwrap();
+
+    let bytes = fs::read(&path).unwrap();
+    let h = Header::parse(&bytes).unwrap();
+    assert_eq!((h.id_width(), h.offset_width()), (4, 4));
+    assert_eq!(bytes.len(), h.file_len().unwrap());
+
+    /*The usize loader refuses the file, the u32 one reads it*/
+    match FastCSR::open(&path) {
+        Err(Error::Format(FormatError::IndexWidth { id: 4, offset: 4 })) => (),
+        _ => panic!("expected IndexWidth"),
+    }
+    let fcsr = FastCSR::<u32, u32>::open_as(&path, Validation::Parallel).unwrap();
+    for v in 0..NUMV {
+        let nbs: Vec<usize> = fcsr.neighbors(v).iter().map(|n| *n as usize).collect();
+        assert_eq!(&nbs[..], csr.in_neighbors(v));
+        assert_eq!(fcsr.in_degree(v), csr.out_degree(v));
+    }
+
+    match FastCSR::open_any(&path, Validation::Parallel).unwrap() {
+        AnyFastCSR::Narrow(g) => assert_eq!(g.gete(), csr.get_e()),
+        _ => panic!("expected 4-byte ids and offsets"),
+    }
+
+    /*Ids and offsets have separate widths*/
+    let mixed = csr.with_index::<u32, usize>().unwrap();
+    mixed.write_fastcsr(p).unwrap();
+    let any = FastCSR::open_any(&path, Validation::Parallel).unwrap();
+    let _ = fs::remove_file(&path);
+    assert_eq!((any.id_width(), any.offset_width()), (4, 8));
+    assert_eq!(any.gete(), mixed.get_e());
+    for v in 0..NUMV {
+        assert_eq!(any.out_degree(v), mixed.out_degree(v));
+    }
+}
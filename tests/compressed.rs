@@ -0,0 +1,124 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::compressed::CompressedFastCSR;
+use raphy::csr::CSR;
+use raphy::error::FormatError;
+use raphy::fast_csr::{FastCSR, Validation};
+use raphy::format::Header;
+use raphy::Error;
+use std::convert::TryInto;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn test_compressed_roundtrip() {
+    const NUMV: usize = 2000;
+    /*Mostly nearby neighbors, as in a graph with locality, plus self-loops,
+    repeats and neighbors before the vertex*/
+    let mut el = CSR::random_el(NUMV, 6);
+    el.extend((0..NUMV).map(|v| (v, (v + 1) % NUMV)));
+    el.extend((0..NUMV).step_by(7).map(|v| (v, v)));
+    el.extend((1..NUMV).step_by(5).map(|v| (v, v - 1)));
+    el.extend((1..NUMV).step_by(5).map(|v| (v, v - 1)));
+    let csr = CSR::new(NUMV, el);
+
+    let dir = std::env::temp_dir();
+    let plain = dir.join("raphy_compressed_plain.csr");
+    let packed = dir.join("raphy_compressed.csr");
+    csr.write_fastcsr(plain.to_str().unwrap().to_string()).unwrap();
+    csr.write_compressed_fastcsr(packed.to_str().unwrap().to_string()).unwrap();
+
+    let g = CompressedFastCSR::open(&packed).unwrap();
+    assert_eq!((g.getv(), g.gete()), (NUMV, csr.get_e()));
+    assert!(fs::metadata(&packed).unwrap().len() < fs::metadata(&plain).unwrap().len());
+
+    for v in 0..NUMV {
+        let (st, en) = csr.vtx_offset_range(v);
+        let mut want = csr.get_neighbs()[st..en].to_vec();
+        want.sort_unstable();
+        let nbs = g.neighbors(v);
+        assert_eq!(nbs.len(), want.len());
+        assert_eq!(nbs.collect::<Vec<_>>(), want);
+        assert_eq!(g.out_degree(v), want.len());
+    }
+
+    let edges = AtomicUsize::new(0);
+    g.read_only_scan(|v0, v1| {
+        assert!(csr.has_edge(v0, v1));
+        edges.fetch_add(1, Ordering::Relaxed);
+    });
+    assert_eq!(edges.into_inner(), csr.get_e());
+
+    let mut smallest = vec![0usize; NUMV];
+    g.neighbor_scan_prop(|v, mut nbs| nbs.next().unwrap_or(v), &mut smallest);
+    for (v, s) in smallest.iter().enumerate() {
+        assert_eq!(*s, g.neighbors(v).min().unwrap_or(v));
+    }
+
+    /*A FastCSR compresses to the same file*/
+    let fcsr = FastCSR::open(&plain).unwrap();
+    let again = dir.join("raphy_compressed_again.csr");
+    let h = fcsr.write_compressed(&again).unwrap();
+    assert!(h.is_compressed());
+    assert_eq!(fs::read(&again).unwrap(), fs::read(&packed).unwrap());
+
+    let _ = fs::remove_file(&plain);
+    let _ = fs::remove_file(&packed);
+    let _ = fs::remove_file(&again);
+}
+
+#[test]
+fn test_compressed_errors() {
+    let csr = CSR::new(4, vec![(0, 1), (0, 3), (1, 2), (3, 0)]);
+    let dir = std::env::temp_dir();
+    let plain = dir.join("raphy_compressed_err_plain.csr");
+    let packed = dir.join("raphy_compressed_err.csr");
+    csr.write_fastcsr(plain.to_str().unwrap().to_string()).unwrap();
+    csr.write_compressed_fastcsr(packed.to_str().unwrap().to_string()).unwrap();
+
+    /*Each loader refuses the other's files*/
+    match FastCSR::open(&packed) {
+        Err(Error::Format(FormatError::Compressed(true))) => (),
+        _ => panic!("expected Compressed(true)"),
+    }
+    match CompressedFastCSR::open(&plain) {
+        Err(Error::Format(FormatError::Compressed(false))) => (),
+        _ => panic!("expected Compressed(false)"),
+    }
+
+    /*Point vertex 1's first neighbor past the end of the graph*/
+    let mut bytes = fs::read(&packed).unwrap();
+    let h = Header::parse(&bytes).unwrap();
+    let off1 = h.neighbs_off + usize::from_ne_bytes(bytes[h.offsets_off + 8..h.offsets_off + 16].try_into().unwrap());
+    bytes[off1 + 1] = 40;
+    fs::write(&packed, &bytes).unwrap();
+    assert!(CompressedFastCSR::open_with(&packed, Validation::Header).is_ok());
+    match CompressedFastCSR::open_with(&packed, Validation::Sequential) {
+        Err(Error::Format(FormatError::NeighborOutOfRange { vtx: 1, .. })) => (),
+        _ => panic!("expected NeighborOutOfRange"),
+    }
+
+    /*Claim vertex 1 has more neighbors than it encodes*/
+    bytes[off1 + 1] = 2;
+    bytes[off1] = 3;
+    fs::write(&packed, &bytes).unwrap();
+    match CompressedFastCSR::open_with(&packed, Validation::Parallel) {
+        Err(Error::Format(FormatError::BadEncoding { vtx: 1 })) => (),
+        _ => panic!("expected BadEncoding"),
+    }
+
+    let _ = fs::remove_file(&plain);
+    let _ = fs::remove_file(&packed);
+}
@@ -0,0 +1,106 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+mod common;
+use common::write_el;
+use raphy::convert::el_to_fastcsr;
+use raphy::csr::{BuildOptions, CSR};
+use raphy::fast_csr::FastCSR;
+use raphy::Error;
+use std::fs;
+
+/*Convert el with opts and check the file against an in-memory build*/
+fn check(el: &[(usize, usize)], numv: usize, opts: BuildOptions) {
+    let dir = std::env::temp_dir();
+    let elpath = dir.join(format!("raphy_convert_{}.el", std::process::id()));
+    let out = dir.join(format!("raphy_convert_{}.csr", std::process::id()));
+    write_el(&elpath, el);
+
+    let h = el_to_fastcsr(&elpath, &out, None, &opts).unwrap();
+    let csr = CSR::new_with(numv, el.to_vec(), &opts);
+    let fcsr = FastCSR::open(&out).unwrap();
+    assert_eq!((h.v, h.e), (csr.get_v(), csr.get_e()));
+    assert_eq!((fcsr.getv(), fcsr.gete()), (csr.get_v(), csr.get_e()));
+    assert_eq!(fs::metadata(&out).unwrap().len() as usize, h.file_len().unwrap());
+
+    /*Unsorted builds may order neighbors differently, so compare as sets*/
+    for v in 0..numv {
+        let (st, en) = csr.vtx_offset_range(v);
+        let mut a = fcsr.neighbors(v).to_vec();
+        let mut b = csr.get_neighbs()[st..en].to_vec();
+        if !(opts.sort || opts.dedup || opts.deterministic) {
+            a.sort_unstable();
+            b.sort_unstable();
+        }
+        assert_eq!(a, b);
+    }
+
+    let _ = fs::remove_file(&elpath);
+    let _ = fs::remove_file(&out);
+}
+
+#[test]
+fn test_el_to_fastcsr() {
+    const NUMV: usize = 1000;
+    let mut el = CSR::random_el(NUMV, 20);
+    /*Make sure the largest id appears so the inferred count is NUMV*/
+    el.push((0, NUMV - 1));
+
+    check(&el, NUMV, BuildOptions::default());
+    check(
+        &el,
+        NUMV,
+        BuildOptions {
+            deterministic: true,
+            ..Default::default()
+        },
+    );
+    check(
+        &el,
+        NUMV,
+        BuildOptions {
+            propagation_blocking: true,
+            sort: true,
+            ..Default::default()
+        },
+    );
+    check(
+        &el,
+        NUMV,
+        BuildOptions {
+            symmetrize: true,
+            remove_self_loops: true,
+            dedup: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[test]
+fn test_el_to_fastcsr_errors() {
+    let dir = std::env::temp_dir();
+    let elpath = dir.join("raphy_convert_bad.el");
+    let out = dir.join("raphy_convert_bad.csr");
+    write_el(&elpath, &[(0, 1), (1, 9)]);
+
+    match el_to_fastcsr(&elpath, &out, Some(5), &BuildOptions::default()) {
+        Err(Error::VertexOutOfRange { vtx: 9, v: 5 }) => (),
+        r => panic!("expected a vertex range error, got {:?}", r.err()),
+    }
+    let h = el_to_fastcsr(&elpath, &out, Some(10), &BuildOptions::default()).unwrap();
+    assert_eq!((h.v, h.e), (10, 2));
+
+    let _ = fs::remove_file(&elpath);
+    let _ = fs::remove_file(&out);
+}
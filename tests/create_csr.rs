@@ -11,10 +11,14 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+#![allow(clippy::write_with_newline)]
+
 extern crate rand;
 extern crate raphy;
 use crate::rand::Rng;
 use raphy::csr::CSR;
+use raphy::error::FormatError;
+use raphy::Error;
 use std::fs::{self, File};
 use std::io::{BufWriter, Write};
 
@@ -33,7 +37,7 @@ fn test_csr_build() {
         }
     }
 
-    let (numv2, el2) = CSR::el_from_file("tmp.el");
+    let (numv2, el2) = CSR::el_from_file("tmp.el").unwrap();
     assert_eq!(el.len(), el2.len());
 
     let csr = CSR::new(NUMV, el);
@@ -59,3 +63,63 @@ fn test_csr_build() {
         b.len()
     );
 }
+
+#[test]
+fn test_csr_load_errors() {
+    match CSR::el_from_file("does_not_exist.el") {
+        Err(Error::Io(_)) => (),
+        r => panic!("expected an i/o error, got {:?}", r.err()),
+    }
+
+    let path = std::env::temp_dir().join("raphy_bad_text.el");
+    fs::write(&path, "0,1\n1,2\n2,x\n").unwrap();
+    match CSR::el_from_file(path.to_str().unwrap()) {
+        Err(Error::Parse { line: 3, .. }) => (),
+        r => panic!("expected a parse error on line 3, got {:?}", r.err()),
+    }
+
+    /*A binary edge list naming vertex 5 in a 4 vertex graph*/
+    let bytes: Vec<u8> = [0usize, 1, 2, 5]
+        .iter()
+        .flat_map(|w| w.to_ne_bytes().to_vec())
+        .collect();
+    fs::write(&path, &bytes).unwrap();
+    match CSR::new_from_el_mmap(4, path.to_str().unwrap().to_string()) {
+        Err(Error::VertexOutOfRange { vtx: 5, v: 4 }) => (),
+        r => panic!("expected a vertex range error, got {:?}", r.err()),
+    }
+    assert!(CSR::new_from_el_mmap(6, path.to_str().unwrap().to_string()).is_ok());
+
+    /*Half an edge*/
+    fs::write(&path, &bytes[..24]).unwrap();
+    match CSR::new_from_el_mmap(6, path.to_str().unwrap().to_string()) {
+        Err(Error::Format(FormatError::EdgeListLength(24))) => (),
+        r => panic!("expected an edge list length error, got {:?}", r.err()),
+    }
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_csr_infer_numv() {
+    let path = std::env::temp_dir().join("raphy_infer.el");
+    let name = path.to_str().unwrap().to_string();
+    let bytes: Vec<u8> = [0usize, 1, 7, 2, 3, 7]
+        .iter()
+        .flat_map(|w| w.to_ne_bytes().to_vec())
+        .collect();
+    fs::write(&path, &bytes).unwrap();
+
+    let csr = CSR::new_from_el_mmap_auto(name.clone(), None).unwrap();
+    assert_eq!(csr.get_v(), 8);
+    assert_eq!(csr.get_e(), 3);
+    assert!(CSR::new_from_el_mmap_auto(name.clone(), Some(8)).is_ok());
+    match CSR::new_from_el_mmap_auto(name.clone(), Some(7)) {
+        Err(Error::VertexOutOfRange { vtx: 7, v: 7 }) => (),
+        r => panic!("expected a vertex range error, got {:?}", r.err()),
+    }
+
+    fs::write(&path, b"").unwrap();
+    assert_eq!(CSR::new_from_el_mmap_auto(name, None).unwrap().get_v(), 0);
+    let _ = fs::remove_file(&path);
+}
@@ -11,6 +11,12 @@ See the License for the specific language governing permissions and
 limitations under the License.
 */
 
+#![allow(
+    clippy::assign_op_pattern,
+    clippy::len_zero,
+    clippy::unnecessary_cast,
+)]
+
 extern crate rand;
 use rand::Rng;
 
@@ -0,0 +1,78 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::{BuildOptions, CSR};
+
+const DETERMINISTIC: BuildOptions = BuildOptions {
+    symmetrize: false,
+    remove_self_loops: false,
+    dedup: false,
+    sort: false,
+    deterministic: true,
+    propagation_blocking: false,
+};
+
+fn bfs_order(csr: &CSR<f64, u32>) -> Vec<usize> {
+    let mut order = Vec::new();
+    csr.bfs_traversal(0, |v| order.push(v));
+    order
+}
+
+#[test]
+fn test_deterministic_build() {
+    const NUMV: usize = 2000;
+    let el: Vec<(usize, usize, u32)> = CSR::random_el(NUMV, 40)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (v0, v1))| (v0, v1, i as u32))
+        .collect();
+
+    let a = CSR::new_weighted_with(NUMV, el.clone(), &DETERMINISTIC);
+    let b = CSR::new_weighted_with(NUMV, el.clone(), &DETERMINISTIC);
+    assert_eq!(a.get_neighbs(), b.get_neighbs());
+    assert_eq!(a.get_weights(), b.get_weights());
+    assert_eq!(bfs_order(&a), bfs_order(&b));
+
+    /*Each vertex's neighbors are in edge list order, which the weights
+    (the edge's index in the list) make visible*/
+    for v in 0..NUMV {
+        let (nbs, wts) = a.weighted_neighbors(v);
+        let expect: Vec<(usize, u32)> = el
+            .iter()
+            .filter(|e| e.0 == v)
+            .map(|e| (e.1, e.2))
+            .collect();
+        let got: Vec<(usize, u32)> = nbs.iter().cloned().zip(wts.iter().cloned()).collect();
+        assert_eq!(got, expect);
+    }
+}
+
+#[test]
+fn test_deterministic_symmetrize() {
+    let el = vec![(2, 0), (0, 3), (1, 0), (0, 0), (0, 1)];
+    let csr = CSR::new_with(
+        4,
+        el,
+        &BuildOptions {
+            symmetrize: true,
+            ..DETERMINISTIC
+        },
+    );
+
+    /*Original edges first, in order, then the reverse edges in order*/
+    let (st, en) = csr.vtx_offset_range(0);
+    assert_eq!(&csr.get_neighbs()[st..en], &[3, 0, 1, 2, 1]);
+    let (st, en) = csr.vtx_offset_range(1);
+    assert_eq!(&csr.get_neighbs()[st..en], &[0, 0]);
+}
@@ -0,0 +1,169 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+mod common;
+use common::sorted;
+use raphy::csr::CSR;
+use raphy::dfs::DfsVisitor;
+use raphy::fast_csr::{FastCSR, Validation};
+use std::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Event {
+    Root(usize),
+    Discover(usize),
+    Finish(usize),
+    Tree(usize, usize),
+    Back(usize, usize),
+    Other(usize, usize),
+}
+
+/*Records every event in order*/
+#[derive(Default)]
+struct Log(Vec<Event>);
+
+impl DfsVisitor for Log {
+    fn root(&mut self, v: usize) {
+        self.0.push(Event::Root(v));
+    }
+    fn discover(&mut self, v: usize) {
+        self.0.push(Event::Discover(v));
+    }
+    fn finish(&mut self, v: usize) {
+        self.0.push(Event::Finish(v));
+    }
+    fn tree_edge(&mut self, u: usize, v: usize) {
+        self.0.push(Event::Tree(u, v));
+    }
+    fn back_edge(&mut self, u: usize, v: usize) {
+        self.0.push(Event::Back(u, v));
+    }
+    fn forward_or_cross_edge(&mut self, u: usize, v: usize) {
+        self.0.push(Event::Other(u, v));
+    }
+}
+
+/*Topological sort: the reverse of the finish order, if there is no cycle*/
+#[derive(Default)]
+struct TopoSort {
+    order: Vec<usize>,
+    cyclic: bool,
+}
+
+impl DfsVisitor for TopoSort {
+    fn finish(&mut self, v: usize) {
+        self.order.push(v);
+    }
+    fn back_edge(&mut self, _u: usize, _v: usize) {
+        self.cyclic = true;
+    }
+}
+
+#[test]
+fn test_dfs_events() {
+    use Event::*;
+    let csr = CSR::new_with(6, vec![(0, 1), (0, 2), (1, 2), (2, 0), (3, 2), (3, 4), (4, 4)], &sorted());
+
+    let mut log = Log::default();
+    csr.dfs(0, &mut log);
+    assert_eq!(
+        log.0,
+        vec![
+            Root(0),
+            Discover(0),
+            Tree(0, 1),
+            Discover(1),
+            Tree(1, 2),
+            Discover(2),
+            Back(2, 0),
+            Finish(2),
+            Finish(1),
+            Other(0, 2),
+            Finish(0),
+        ]
+    );
+
+    let mut log = Log::default();
+    csr.dfs_all(&mut log);
+    assert_eq!(
+        log.0[11..].to_vec(),
+        vec![
+            Root(3),
+            Discover(3),
+            Other(3, 2),
+            Tree(3, 4),
+            Discover(4),
+            Back(4, 4),
+            Finish(4),
+            Finish(3),
+            Root(5),
+            Discover(5),
+            Finish(5),
+        ]
+    );
+
+    /*A FastCSR keeps the neighbor order, so it sees the same events*/
+    let path = std::env::temp_dir().join("raphy_dfs.csr");
+    csr.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let fcsr = FastCSR::open(&path).unwrap();
+    let any = FastCSR::open_any(&path, Validation::Parallel).unwrap();
+    let _ = fs::remove_file(&path);
+    let mut flog = Log::default();
+    fcsr.dfs_all(&mut flog);
+    assert_eq!(flog.0, log.0);
+    let mut alog = Log::default();
+    any.dfs_all(&mut alog);
+    assert_eq!(alog.0, log.0);
+}
+
+#[test]
+fn test_dfs_topological_sort() {
+    const NUMV: usize = 2000;
+    /*Edges only go from lower to higher ids, so the graph is acyclic*/
+    let el: Vec<(usize, usize)> = CSR::random_el(NUMV, 8)
+        .into_iter()
+        .filter(|(v0, v1)| v0 < v1)
+        .collect();
+    let csr = CSR::new(NUMV, el.clone());
+
+    let mut t = TopoSort::default();
+    csr.dfs_all(&mut t);
+    assert!(!t.cyclic);
+    assert_eq!(t.order.len(), NUMV);
+    let mut pos = vec![0; NUMV];
+    for (i, v) in t.order.iter().rev().enumerate() {
+        pos[*v] = i;
+    }
+    assert!(el.iter().all(|(v0, v1)| pos[*v0] < pos[*v1]));
+
+    /*One edge back closes a cycle*/
+    let (v0, v1) = el[0];
+    let mut cyclic = el;
+    cyclic.push((v1, v0));
+    let mut t = TopoSort::default();
+    CSR::new(NUMV, cyclic).dfs_all(&mut t);
+    assert!(t.cyclic);
+}
+
+#[test]
+fn test_dfs_deep_path() {
+    /*A path far longer than recursion could follow*/
+    const NUMV: usize = 1_000_000;
+    let csr = CSR::new(NUMV, (0..NUMV - 1).map(|v| (v, v + 1)).collect());
+
+    let mut t = TopoSort::default();
+    csr.dfs(0, &mut t);
+    assert_eq!(t.order.len(), NUMV);
+    assert!(t.order.iter().rev().enumerate().all(|(i, v)| i == *v));
+}
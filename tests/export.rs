@@ -0,0 +1,114 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::{BuildOptions, CSR};
+use raphy::export::{self, ExportOptions};
+use raphy::fast_csr::FastCSR;
+use raphy::graph::Graph;
+use std::fs;
+
+#[test]
+fn test_export_dot() {
+    let sorted = BuildOptions {
+        sort: true,
+        ..Default::default()
+    };
+    let mut g = CSR::<f64, u32>::new_weighted_with(3, vec![(0, 1, 5), (0, 2, 7), (1, 2, 1)], &sorted)
+        .with_vtxprop(0usize);
+    g.get_mut_vtxprop().copy_from_slice(&[0, 1, 1]);
+
+    let opts = ExportOptions {
+        vertices: &[0, 2],
+        edges: &[(0, 2)],
+    };
+    let mut out = Vec::new();
+    export::dot(&g, &mut out, &opts).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "digraph G {\n\
+         \x20 0 [label=\"0: 0\", value=\"0\", style=filled, fillcolor=gold];\n\
+         \x20 1 [label=\"1: 1\", value=\"1\"];\n\
+         \x20 2 [label=\"2: 1\", value=\"1\", style=filled, fillcolor=gold];\n\
+         \x20 0 -> 1 [label=\"5\"];\n\
+         \x20 0 -> 2 [label=\"7\", color=red, penwidth=2];\n\
+         \x20 1 -> 2 [label=\"1\"];\n\
+         }\n"
+    );
+
+    /*A FastCSR has no values of its own, but can be given some*/
+    let path = std::env::temp_dir().join("raphy_export.csr");
+    let dot = std::env::temp_dir().join("raphy_export.dot");
+    CSR::new_with(3, vec![(0, 1), (2, 0)], &sorted)
+        .write_fastcsr(path.to_str().unwrap().to_string())
+        .unwrap();
+    let f = FastCSR::open(&path).unwrap();
+    export::write_dot(&f, &dot, &ExportOptions::default()).unwrap();
+    assert_eq!(
+        fs::read_to_string(&dot).unwrap(),
+        "digraph G {\n  0 [label=\"0\"];\n  1 [label=\"1\"];\n  2 [label=\"2\"];\n  0 -> 1;\n  2 -> 0;\n}\n"
+    );
+    let names = ["root", "a \"quoted\" leaf", "c"];
+    let named = export::with_values(&f, &names).unwrap();
+    export::write_dot(&named, &dot, &ExportOptions::default()).unwrap();
+    let text = fs::read_to_string(&dot).unwrap();
+    assert!(text.contains("  1 [label=\"1: a \\\"quoted\\\" leaf\", value=\"a \\\"quoted\\\" leaf\"];\n"));
+    assert!(export::with_values(&f, &names[1..]).is_err());
+
+    /*A weighted FastCSR exports its weights, as the CSR it came from does*/
+    g.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let f = FastCSR::open(&path).unwrap();
+    export::write_dot(&f, &dot, &ExportOptions::default()).unwrap();
+    assert_eq!(
+        fs::read_to_string(&dot).unwrap(),
+        "digraph G {\n\
+         \x20 0 [label=\"0\"];\n\
+         \x20 1 [label=\"1\"];\n\
+         \x20 2 [label=\"2\"];\n\
+         \x20 0 -> 1 [label=\"5\"];\n\
+         \x20 0 -> 2 [label=\"7\"];\n\
+         \x20 1 -> 2 [label=\"1\"];\n\
+         }\n"
+    );
+
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(&dot);
+}
+
+#[test]
+fn test_export_graphml() {
+    let mut g: Graph<String> = Graph::new();
+    g.add_vtx(0, "x<y".to_string());
+    g.add_vtx(1, "b&c".to_string());
+    g.add_edge(0, 1);
+    g.add_edge(1, 0);
+
+    let path = std::env::temp_dir().join("raphy_export.graphml");
+    let opts = ExportOptions {
+        vertices: &[1],
+        edges: &[(1, 0)],
+    };
+    export::write_graphml(&g, &path, &opts).unwrap();
+    let text = fs::read_to_string(&path).unwrap();
+    assert!(text.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<graphml"));
+    assert!(text.contains("<graph id=\"G\" edgedefault=\"directed\">"));
+    assert!(text.contains("    <node id=\"n0\"><data key=\"value\">x&lt;y</data></node>\n"));
+    assert!(text.contains(
+        "    <node id=\"n1\"><data key=\"value\">b&amp;c</data><data key=\"vhighlight\">true</data></node>\n"
+    ));
+    assert!(text.contains("    <edge source=\"n0\" target=\"n1\"></edge>\n"));
+    assert!(text.contains("    <edge source=\"n1\" target=\"n0\"><data key=\"ehighlight\">true</data></edge>\n"));
+    assert!(text.ends_with("  </graph>\n</graphml>\n"));
+
+    let _ = fs::remove_file(&path);
+}
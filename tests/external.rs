@@ -0,0 +1,115 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+mod common;
+use common::write_el;
+use raphy::csr::{BuildOptions, CSR};
+use raphy::external::ExternalBuilder;
+use raphy::fast_csr::FastCSR;
+use raphy::Error;
+use std::fs;
+
+#[test]
+fn test_external_build() {
+    const NUMV: usize = 500;
+    let el = CSR::random_el(NUMV, 30);
+    let dir = std::env::temp_dir();
+    let elpath = dir.join("raphy_external.el");
+    let out = dir.join("raphy_external.csr");
+    write_el(&elpath, &el);
+
+    /*Small chunks force many buckets and many reads of the edge list*/
+    let h = ExternalBuilder::new()
+        .chunk_edges(97)
+        .numv(NUMV)
+        .build(&elpath, &out)
+        .unwrap();
+    assert_eq!((h.v, h.e), (NUMV, el.len()));
+
+    let det = BuildOptions {
+        deterministic: true,
+        ..Default::default()
+    };
+    let csr = CSR::new_with(NUMV, el.clone(), &det);
+    let fcsr = FastCSR::open(&out).unwrap();
+    for v in 0..NUMV {
+        let (st, en) = csr.vtx_offset_range(v);
+        assert_eq!(fcsr.neighbors(v), &csr.get_neighbs()[st..en]);
+    }
+
+    /*Reverse edges follow the original ones, as in a deterministic build*/
+    let sym = BuildOptions {
+        symmetrize: true,
+        deterministic: true,
+        ..Default::default()
+    };
+    ExternalBuilder::new()
+        .chunk_edges(61)
+        .numv(NUMV)
+        .options(sym)
+        .build(&elpath, &out)
+        .unwrap();
+    let csr = CSR::new_with(NUMV, el.clone(), &sym);
+    let fcsr = FastCSR::open(&out).unwrap();
+    assert_eq!(fcsr.gete(), csr.get_e());
+    for v in 0..NUMV {
+        let (st, en) = csr.vtx_offset_range(v);
+        assert_eq!(fcsr.neighbors(v), &csr.get_neighbs()[st..en]);
+    }
+
+    let opts = BuildOptions {
+        symmetrize: true,
+        remove_self_loops: true,
+        dedup: true,
+        ..Default::default()
+    };
+    ExternalBuilder::new()
+        .chunk_edges(50)
+        .options(opts)
+        .build(&elpath, &out)
+        .unwrap();
+    let csr = CSR::new_with(NUMV, el, &opts);
+    let fcsr = FastCSR::open(&out).unwrap();
+    assert!(fcsr.is_sorted());
+    assert_eq!(fcsr.gete(), csr.get_e());
+    for v in 0..fcsr.getv() {
+        let (st, en) = csr.vtx_offset_range(v);
+        assert_eq!(fcsr.neighbors(v), &csr.get_neighbs()[st..en]);
+    }
+
+    let _ = fs::remove_file(&elpath);
+    let _ = fs::remove_file(&out);
+}
+
+#[test]
+fn test_external_build_errors() {
+    let dir = std::env::temp_dir();
+    let elpath = dir.join("raphy_external_bad.el");
+    let out = dir.join("raphy_external_bad.csr");
+    write_el(&elpath, &[(0, 1), (4, 2)]);
+
+    match ExternalBuilder::new().numv(4).build(&elpath, &out) {
+        Err(Error::VertexOutOfRange { vtx: 4, v: 4 }) => (),
+        r => panic!("expected a vertex range error, got {:?}", r.err()),
+    }
+
+    let h = ExternalBuilder::new().build(&elpath, &out).unwrap();
+    assert_eq!(h.v, 5);
+    let fcsr = FastCSR::open(&out).unwrap();
+    assert_eq!(fcsr.neighbors(4), &[2]);
+    assert!(fcsr.neighbors(3).is_empty());
+
+    let _ = fs::remove_file(&elpath);
+    let _ = fs::remove_file(&out);
+}
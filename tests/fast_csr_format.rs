@@ -0,0 +1,119 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::{BuildOptions, CSR};
+use raphy::error::FormatError;
+use raphy::fast_csr::FastCSR;
+use raphy::format::{Header, HEADER_BYTES};
+use raphy::Error;
+use std::fs;
+
+#[test]
+fn test_fastcsr_v2_roundtrip() {
+    const NUMV: usize = 1000;
+    const MAXE: usize = 10;
+    let csr = CSR::new(NUMV, CSR::random_el(NUMV, MAXE));
+
+    let path = std::env::temp_dir().join("raphy_v2_roundtrip.csr");
+    csr.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+
+    let bytes = fs::read(&path).unwrap();
+    let h = Header::parse(&bytes).unwrap();
+    assert_eq!(h.v, NUMV);
+    assert_eq!(h.e, csr.get_e());
+    assert_eq!(h.offsets_off, HEADER_BYTES);
+    assert!(h.is_directed());
+    assert!(!h.is_weighted());
+    assert_eq!(bytes.len(), h.file_len().unwrap());
+
+    let fcsr = FastCSR::new(path.to_str().unwrap().to_string()).unwrap();
+    let _ = fs::remove_file(&path);
+
+    assert_eq!(fcsr.getv(), csr.get_v());
+    assert_eq!(fcsr.gete(), csr.get_e());
+    for v in 0..NUMV {
+        let (st, en) = csr.vtx_offset_range(v);
+        assert_eq!(fcsr.neighbors(v), &csr.get_neighbs()[st..en]);
+    }
+}
+
+#[test]
+fn test_fastcsr_symmetric() {
+    /*A symmetrized graph is written undirected, and stays so when it is
+    compressed or transposed*/
+    let opts = BuildOptions {
+        symmetrize: true,
+        ..Default::default()
+    };
+    let csr = CSR::new_with(4, vec![(0, 1), (1, 2), (3, 0)], &opts);
+    assert!(csr.is_symmetric());
+    assert!(csr.transpose().is_symmetric());
+
+    let path = std::env::temp_dir().join("raphy_v2_symmetric.csr");
+    csr.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let fcsr = FastCSR::open(&path).unwrap();
+    assert!(!fcsr.is_directed());
+    let cpath = std::env::temp_dir().join("raphy_v2_symmetric.ccsr");
+    assert!(!fcsr.write_compressed(&cpath).unwrap().is_directed());
+    let _ = fs::remove_file(&cpath);
+
+    let csr = CSR::new(4, vec![(0, 1), (1, 2), (3, 0)]);
+    assert!(!csr.is_symmetric());
+    csr.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    assert!(FastCSR::open(&path).unwrap().is_directed());
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_fastcsr_legacy() {
+    let fcsr = FastCSR::new_legacy(String::from("graphs/tiny.csr")).unwrap();
+    assert_eq!(fcsr.getv(), 10);
+    assert_eq!(fcsr.gete(), 20);
+    assert_eq!(fcsr.neighbors(0), &[1, 6]);
+}
+
+#[test]
+fn test_fastcsr_rejects_legacy() {
+    match FastCSR::new(String::from("graphs/tiny.csr")) {
+        Err(Error::Format(FormatError::BadMagic)) => (),
+        r => panic!("expected a bad magic error, got {:?}", r.err()),
+    }
+}
+
+#[test]
+fn test_write_fastcsr_atomic() {
+    let dir = std::env::temp_dir().join(format!("raphy_atomic_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("g.csr");
+    let name = path.to_str().unwrap().to_string();
+
+    /*Overwriting a graph replaces it whole and leaves no temporary files*/
+    CSR::new(3, vec![(0, 1), (1, 2)]).write_fastcsr(name.clone()).unwrap();
+    let big = CSR::new(100, CSR::random_el(100, 10));
+    big.write_fastcsr(name.clone()).unwrap();
+    let fcsr = FastCSR::open(&path).unwrap();
+    assert_eq!((fcsr.getv(), fcsr.gete()), (100, big.get_e()));
+    let files: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+    assert_eq!(files.len(), 1);
+
+    /*A write that cannot happen fails with an error and changes nothing*/
+    let missing = dir.join("no_such_dir").join("g.csr");
+    match big.write_fastcsr(missing.to_str().unwrap().to_string()) {
+        Err(Error::Io(_)) => (),
+        r => panic!("expected an i/o error, got {:?}", r),
+    }
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    let _ = fs::remove_dir_all(&dir);
+}
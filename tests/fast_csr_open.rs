@@ -0,0 +1,141 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::CSR;
+use raphy::fast_csr::{FastCSR, Validation};
+use raphy::format::HEADER_BYTES;
+use raphy::error::FormatError;
+use raphy::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/*Write a small graph in the v2 format and hand back its path and bytes*/
+fn write_graph(name: &str) -> (PathBuf, Vec<u8>) {
+    let el = vec![(0, 1), (0, 2), (1, 2), (2, 0), (3, 1)];
+    let csr = CSR::new(4, el);
+    let path = std::env::temp_dir().join(name);
+    csr.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    (path, bytes)
+}
+
+fn word(bytes: &mut [u8], i: usize, val: usize) {
+    bytes[i * 8..(i + 1) * 8].copy_from_slice(&val.to_ne_bytes());
+}
+
+#[test]
+fn test_open_valid() {
+    let (path, _) = write_graph("raphy_open_valid.csr");
+    let g = FastCSR::open(&path).unwrap();
+    let g2 = FastCSR::open_with(&path, Validation::Sequential).unwrap();
+    let _ = fs::remove_file(&path);
+
+    assert_eq!(g.getv(), 4);
+    assert_eq!(g.gete(), 5);
+    assert_eq!(g2.neighbors(0), g.neighbors(0));
+    FastCSR::open_legacy("graphs/tiny.csr", Validation::Parallel).unwrap();
+}
+
+#[test]
+fn test_open_errors() {
+    match FastCSR::open("graphs/does_not_exist.csr") {
+        Err(Error::Io(_)) => (),
+        r => panic!("expected an i/o error, got {:?}", r.err()),
+    }
+
+    match FastCSR::open("graphs/tiny.csr") {
+        Err(Error::Format(FormatError::BadMagic)) => (),
+        r => panic!("expected a bad magic error, got {:?}", r.err()),
+    }
+
+    let (path, bytes) = write_graph("raphy_open_errors.csr");
+
+    /*Chop off the last edge*/
+    fs::write(&path, &bytes[..bytes.len() - 8]).unwrap();
+    match FastCSR::open(&path) {
+        Err(Error::Format(FormatError::Truncated { .. })) => (),
+        r => panic!("expected a truncation error, got {:?}", r.err()),
+    }
+
+    /*Offsets are words 16.. of the file; make vertex 2's offset smaller than vertex 1's*/
+    let mut bad = bytes.clone();
+    word(&mut bad, HEADER_BYTES / 8 + 2, 0);
+    fs::write(&path, &bad).unwrap();
+    match FastCSR::open(&path) {
+        Err(Error::Format(FormatError::OffsetsNotMonotonic { vtx: 2 })) => (),
+        r => panic!("expected a monotonicity error, got {:?}", r.err()),
+    }
+
+    /*Point vertex 3's last neighbor at a vertex that does not exist*/
+    let mut bad = bytes.clone();
+    let last = bad.len() / 8 - 1;
+    word(&mut bad, last, 7);
+    fs::write(&path, &bad).unwrap();
+    match FastCSR::open_with(&path, Validation::Sequential) {
+        Err(Error::Format(FormatError::NeighborOutOfRange {
+            vtx: 3,
+            neighbor: 7,
+            v: 4,
+        })) => (),
+        r => panic!("expected a neighbor range error, got {:?}", r.err()),
+    }
+    /*Header-only validation does not scan the neighbors*/
+    assert!(FastCSR::open_with(&path, Validation::Header).is_ok());
+
+    /*Sections have to lie after the header and apart from each other, even
+    when only the header is checked; word 6 is the neighbs offset*/
+    let mut bad = bytes.clone();
+    word(&mut bad, 6, 0);
+    fs::write(&path, &bad).unwrap();
+    match FastCSR::open_with(&path, Validation::Header) {
+        Err(Error::Format(FormatError::SectionInHeader {
+            section: "neighbs",
+            off: 0,
+        })) => (),
+        r => panic!("expected a section in the header, got {:?}", r.err()),
+    }
+    let mut bad = bytes.clone();
+    word(&mut bad, 6, HEADER_BYTES + 8);
+    fs::write(&path, &bad).unwrap();
+    match FastCSR::open_with(&path, Validation::Header) {
+        Err(Error::Format(FormatError::SectionsOverlap {
+            first: "offsets",
+            second: "neighbs",
+        })) => (),
+        r => panic!("expected overlapping sections, got {:?}", r.err()),
+    }
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_open_legacy_truncated() {
+    let bytes = fs::read("graphs/tiny.csr").unwrap();
+    let path = std::env::temp_dir().join("raphy_open_legacy_truncated.csr");
+
+    /*10 vertices and 20 edges: chop off the last neighbor, then everything
+    after the offsets*/
+    assert_eq!(bytes.len(), 16 + 10 * 8 + 20 * 8);
+    for len in [bytes.len() - 8, 16 + 10 * 8].iter() {
+        fs::write(&path, &bytes[..*len]).unwrap();
+        for check in [Validation::Header, Validation::Sequential, Validation::Parallel].iter() {
+            match FastCSR::open_legacy(&path, *check) {
+                Err(Error::Format(FormatError::Truncated { .. })) => (),
+                r => panic!("expected a truncation error, got {:?}", r.err()),
+            }
+        }
+    }
+
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,149 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+mod common;
+use common::sorted;
+use raphy::csr::CSR;
+use raphy::error::FormatError;
+use raphy::fast_csr::Validation;
+use raphy::galois::GaloisGraph;
+use raphy::ligra;
+use raphy::Error;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn test_galois_gr() {
+    /*A version 1 file laid out by hand: 3 vertices, 3 edges (so the 4-byte
+    ids are padded), 4-byte edge data*/
+    let mut bytes = Vec::new();
+    for w in [1u64, 4, 3, 3, 2, 2, 3].iter() {
+        bytes.extend_from_slice(&w.to_le_bytes());
+    }
+    for n in [1u32, 2, 0, 0].iter() {
+        bytes.extend_from_slice(&n.to_le_bytes());
+    }
+    for x in [10u32, 20, 30].iter() {
+        bytes.extend_from_slice(&x.to_le_bytes());
+    }
+    let path = std::env::temp_dir().join("raphy_galois.gr");
+    fs::write(&path, &bytes).unwrap();
+
+    let g = GaloisGraph::open(&path).unwrap();
+    assert_eq!((g.getv(), g.gete(), g.edge_data_size()), (3, 3, 4));
+    assert_eq!(g.weighted_neighbors::<u32>(0), (&[1u32, 2][..], &[10u32, 20][..]));
+    assert_eq!(g.neighbors(1), &[] as &[u32]);
+    assert_eq!(g.weighted_neighbors::<u32>(2), (&[0u32][..], &[30u32][..]));
+    match g.check_weights::<u64>() {
+        Err(Error::Format(FormatError::WeightWidth { expected: 8, found: 4 })) => (),
+        r => panic!("expected WeightWidth, got {:?}", r),
+    }
+
+    /*The same graph written from a CSR is the same file*/
+    let csr = CSR::<f64, u32>::new_weighted_with(3, vec![(0, 1, 10), (0, 2, 20), (2, 0, 30)], &sorted());
+    let out = std::env::temp_dir().join("raphy_galois_out.gr");
+    csr.write_galois(out.to_str().unwrap().to_string()).unwrap();
+    assert_eq!(fs::read(&out).unwrap(), bytes);
+
+    /*A bigger unweighted graph*/
+    const NUMV: usize = 1000;
+    let csr = CSR::new_with(NUMV, CSR::random_el(NUMV, 10), &sorted());
+    csr.write_galois(out.to_str().unwrap().to_string()).unwrap();
+    let g = GaloisGraph::open(&out).unwrap();
+    assert_eq!((g.getv(), g.gete(), g.edge_data_size()), (NUMV, csr.get_e(), 0));
+    let edges = AtomicUsize::new(0);
+    g.read_only_scan(|v0, v1| {
+        assert!(csr.has_edge(v0, v1));
+        edges.fetch_add(1, Ordering::Relaxed);
+    });
+    assert_eq!(edges.into_inner(), csr.get_e());
+    let mut degs = vec![0usize; NUMV];
+    g.neighbor_scan_prop(|_, nbs| nbs.len(), &mut degs);
+    assert!((0..NUMV).all(|v| degs[v] == csr.out_degree(v)));
+
+    /*Errors: the wrong id width, an id out of range, a short file*/
+    match GaloisGraph::<u64>::open_as(&path, Validation::Parallel) {
+        Err(Error::Format(FormatError::IndexWidth { id: 4, .. })) => (),
+        r => panic!("expected IndexWidth, got {:?}", r.err()),
+    }
+    bytes[56] = 7;
+    fs::write(&path, &bytes).unwrap();
+    assert!(GaloisGraph::open_with(&path, Validation::Header).is_ok());
+    match GaloisGraph::open_with(&path, Validation::Sequential) {
+        Err(Error::Format(FormatError::NeighborOutOfRange { vtx: 0, neighbor: 7, v: 3 })) => (),
+        r => panic!("expected NeighborOutOfRange, got {:?}", r.err()),
+    }
+    fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    match GaloisGraph::open(&path) {
+        Err(Error::Format(FormatError::Truncated { .. })) => (),
+        r => panic!("expected Truncated, got {:?}", r.err()),
+    }
+
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(&out);
+}
+
+#[test]
+fn test_ligra_adjacency_graph() {
+    let path = std::env::temp_dir().join("raphy_ligra.adj");
+    let path = path.to_str().unwrap().to_string();
+    fs::write(&path, "WeightedAdjacencyGraph\n3\n4\n0\n2\n3\n1\n2\n2\n0 7 8\n9 6\n").unwrap();
+
+    let g = CSR::<f64, i32>::new_weighted_from_ligra(&path, &sorted()).unwrap();
+    assert_eq!((g.get_v(), g.get_e()), (3, 4));
+    assert_eq!(g.weighted_neighbors(0), (&[1, 2][..], &[7, 8][..]));
+    assert_eq!(g.weighted_neighbors(1), (&[2][..], &[9][..]));
+    assert_eq!(g.weighted_neighbors(2), (&[0][..], &[6][..]));
+
+    g.write_ligra(path.clone()).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "WeightedAdjacencyGraph\n3\n4\n0\n2\n3\n1\n2\n2\n0\n7\n8\n9\n6\n"
+    );
+    let u = CSR::new_from_ligra(&path, &sorted()).unwrap();
+    assert_eq!(u.get_neighbs(), g.get_neighbs());
+    u.write_ligra(path.clone()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "AdjacencyGraph\n3\n4\n0\n2\n3\n1\n2\n2\n0\n");
+    let (v, el) = ligra::read_el(&path).unwrap();
+    assert_eq!((v, el), (3, vec![(0, 1), (0, 2), (1, 2), (2, 0)]));
+
+    let cases = [
+        ("AdjacencyGraph\n2\n1\n0\n1\nx\n", 6),
+        ("AdjacencyGraph\n2\n1\n0\n", 4),
+        ("AdjacencyGraph\n2\n1\n1\n1\n0\n", 4),
+        ("AdjacencyGraph\n2\n1\n0\n1\n0\n0\n", 7),
+        ("AdjGraph\n2\n1\n0\n1\n0\n", 1),
+        /*Counts far larger than the file fail before anything is reserved*/
+        ("AdjacencyGraph\n1152921504606846976\n1\n0\n", 3),
+        ("AdjacencyGraph\n18446744073709551615\n1\n0\n", 3),
+    ];
+    for (i, (text, line)) in cases.iter().enumerate() {
+        fs::write(&path, text).unwrap();
+        match ligra::read_el(&path) {
+            Err(Error::Parse { line: l, .. }) if l == *line => (),
+            r => panic!("case {}: expected a parse error on line {}, got {:?}", i, line, r.err()),
+        }
+    }
+    fs::write(&path, "AdjacencyGraph\n2\n1\n0\n1\n2\n").unwrap();
+    match ligra::read_el(&path) {
+        Err(Error::VertexOutOfRange { vtx: 2, v: 2 }) => (),
+        r => panic!("expected VertexOutOfRange, got {:?}", r.err()),
+    }
+    match ligra::read_wel::<u32>(&path) {
+        Err(Error::Parse { line: 1, .. }) => (),
+        r => panic!("expected a parse error, got {:?}", r.err()),
+    }
+
+    let _ = fs::remove_file(&path);
+}
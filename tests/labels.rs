@@ -0,0 +1,150 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::BuildOptions;
+use raphy::error::FormatError;
+use raphy::fast_csr::FastCSR;
+use raphy::labels::{self, Labels};
+use raphy::text_el::TextFormat;
+use raphy::Error;
+use std::fs;
+
+#[test]
+fn test_labeled_fastcsr() {
+    let dir = std::env::temp_dir();
+    let text = dir.join("raphy_labels_el.txt");
+    let out = dir.join("raphy_labels.csr");
+    fs::write(
+        &text,
+        "# users and who they follow\n\
+         alice\tbob\n\
+         bob\t18446744073709551557\n\
+         carol\talice\n\
+         alice\t18446744073709551557\n",
+    )
+    .unwrap();
+
+    let sorted = BuildOptions {
+        sort: true,
+        ..Default::default()
+    };
+    let lbl = labels::labeled_text_to_fastcsr(&text, &out, &TextFormat::snap(), &sorted).unwrap();
+    assert_eq!(lbl.len(), 4);
+    let names = ["alice", "bob", "18446744073709551557", "carol"];
+    for (id, name) in names.iter().enumerate() {
+        assert_eq!(lbl.label(id), *name);
+        assert_eq!(lbl.id(name), Some(id));
+    }
+    assert_eq!(lbl.id("dave"), None);
+
+    /*The graph and its labels reopen together*/
+    let g = FastCSR::open(&out).unwrap();
+    let lbl = Labels::open(labels::labels_path(&out)).unwrap();
+    let alice = lbl.id("alice").unwrap();
+    let follows: Vec<&str> = g.neighbors(alice).iter().map(|v| lbl.label(*v)).collect();
+    assert_eq!(follows, vec!["bob", "18446744073709551557"]);
+
+    /*Results go out under the original labels*/
+    let degs: Vec<usize> = (0..g.getv()).map(|v| g.neighbors(v).len()).collect();
+    let res = dir.join("raphy_labels_degs.txt");
+    lbl.write_values(&res, &degs).unwrap();
+    assert_eq!(
+        fs::read_to_string(&res).unwrap(),
+        "alice\t2\nbob\t1\n18446744073709551557\t0\ncarol\t1\n"
+    );
+
+    /*One value too few is an error, and leaves the earlier results alone*/
+    match lbl.write_values(&res, &degs[1..]) {
+        Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::InvalidInput => (),
+        r => panic!("expected InvalidInput, got {:?}", r.err().map(|e| e.to_string())),
+    }
+    assert!(fs::read_to_string(&res).unwrap().starts_with("alice\t2\n"));
+
+    for p in [&text, &out, &labels::labels_path(&out), &res].iter() {
+        let _ = fs::remove_file(p);
+    }
+}
+
+#[test]
+fn test_labeled_fastcsr_commit() {
+    let dir = std::env::temp_dir();
+    let text = dir.join("raphy_labels_commit.txt");
+    let out = dir.join("raphy_labels_commit.csr");
+    let lpath = labels::labels_path(&out);
+    let opts = BuildOptions::default();
+
+    fs::write(&text, "a b\n").unwrap();
+    labels::labeled_text_to_fastcsr(&text, &out, &TextFormat::snap(), &opts).unwrap();
+    let old = fs::read(&out).unwrap();
+
+    /*A directory in the way of the labels makes their commit fail after the
+    graph's, which has to be undone*/
+    fs::remove_file(&lpath).unwrap();
+    fs::create_dir(&lpath).unwrap();
+    fs::write(lpath.join("x"), "").unwrap();
+    fs::write(&text, "a b\nb c\nc a\n").unwrap();
+    assert!(labels::labeled_text_to_fastcsr(&text, &out, &TextFormat::snap(), &opts).is_err());
+    assert_eq!(fs::read(&out).unwrap(), old);
+
+    /*With no earlier graph, the new one is removed*/
+    fs::remove_file(&out).unwrap();
+    assert!(labels::labeled_text_to_fastcsr(&text, &out, &TextFormat::snap(), &opts).is_err());
+    assert!(!out.exists());
+
+    /*No temporary files are left behind*/
+    let stray = fs::read_dir(&dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .any(|e| e.file_name().to_string_lossy().starts_with(".raphy_labels_commit.csr"));
+    assert!(!stray);
+
+    let _ = fs::remove_dir_all(&lpath);
+    let _ = fs::remove_file(&text);
+}
+
+#[test]
+fn test_labels_errors() {
+    let path = std::env::temp_dir().join("raphy_labels_err.labels");
+    match Labels::write(&path, &["x", "y", "x"]) {
+        Err(Error::Format(FormatError::BadLabel { vtx: 2 })) => (),
+        r => panic!("expected BadLabel, got {:?}", r.err()),
+    }
+
+    Labels::write(&path, &["b", "a", "c"]).unwrap();
+    let mut bytes = fs::read(&path).unwrap();
+    let n = bytes.len();
+
+    /*Swap the order of two labels so the sorted table is out of order*/
+    bytes.swap(n - 3, n - 2);
+    fs::write(&path, &bytes).unwrap();
+    match Labels::open(&path) {
+        Err(Error::Format(FormatError::BadLabel { .. })) => (),
+        r => panic!("expected BadLabel, got {:?}", r.err().map(|e| e.to_string())),
+    }
+
+    fs::write(&path, &bytes[..n - 1]).unwrap();
+    match Labels::open(&path) {
+        Err(Error::Format(FormatError::Truncated { .. })) => (),
+        r => panic!("expected Truncated, got {:?}", r.err().map(|e| e.to_string())),
+    }
+
+    bytes[0] = b'X';
+    fs::write(&path, &bytes).unwrap();
+    match Labels::open(&path) {
+        Err(Error::Format(FormatError::BadMagic)) => (),
+        r => panic!("expected BadMagic, got {:?}", r.err().map(|e| e.to_string())),
+    }
+
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,142 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+mod common;
+use common::{sorted, write_tmp};
+use raphy::csr::CSR;
+use raphy::{dimacs, metis};
+use raphy::Error;
+use std::fs;
+
+#[test]
+fn test_metis() {
+    /*The weighted example graph from the METIS manual*/
+    let path = write_tmp(
+        "raphy_metis.graph",
+        "% the METIS manual's example\n\
+         7 11 001\n\
+         5 1 3 2 2 1\n\
+         1 1 3 2 4 1\n\
+         5 3 4 2 2 2 1 2\n\
+         2 1 3 2 6 2 7 5\n\
+         1 1 3 3 6 2\n\
+         5 2 4 2 7 6\n\
+         6 6 4 5\n",
+    );
+    let g = CSR::<f64, u32>::new_weighted_from_metis(&path, &sorted()).unwrap();
+    assert_eq!((g.get_v(), g.get_e()), (7, 22));
+    assert_eq!(g.weighted_neighbors(0), (&[1, 2, 4][..], &[1, 2, 1][..]));
+    assert_eq!(g.weighted_neighbors(6), (&[3, 5][..], &[5, 6][..]));
+
+    /*Written back out, it reads back the same*/
+    let out = write_tmp("raphy_metis_out.graph", "");
+    g.write_metis(out.clone()).unwrap();
+    let text = fs::read_to_string(&out).unwrap();
+    assert!(text.starts_with("7 11 001\n2 1 3 2 5 1\n"));
+    let again = CSR::<f64, u32>::new_weighted_from_metis(&out, &sorted()).unwrap();
+    assert_eq!(again.get_neighbs(), g.get_neighbs());
+    assert_eq!(again.get_weights(), g.get_weights());
+
+    /*Vertex sizes and weights are skipped, and trailing vertices with no
+    neighbors can be left off*/
+    fs::write(&path, "4 2 110 2\n9 1 1 2\n9 2 2 1 3\n% between\n9 0 0 2\n").unwrap();
+    let (h, el) = metis::read_el(&path).unwrap();
+    assert_eq!((h.v, h.edges, h.vertex_sizes, h.vertex_weights, h.edge_weights), (4, 2, true, 2, false));
+    assert_eq!(h.line(), "4 2 110 2");
+    assert_eq!(el, vec![(0, 1), (1, 0), (1, 2), (2, 1)]);
+
+    /*A blank line is a vertex with no neighbors*/
+    fs::write(&path, "3 1\n\n3\n2\n").unwrap();
+    let (h, el) = metis::read_el(&path).unwrap();
+    assert_eq!((h.v, el), (3, vec![(1, 2), (2, 1)]));
+    match metis::read_wel::<u32>(&path) {
+        Err(Error::Parse { line: 1, .. }) => (),
+        r => panic!("expected a parse error, got {:?}", r.err()),
+    }
+
+    /*Half an edge, and a directed graph, are not METIS graphs*/
+    fs::write(&path, "2 1\n2\n\n").unwrap();
+    match metis::read_el(&path) {
+        Err(Error::Parse { line: 3, .. }) => (),
+        r => panic!("expected a parse error, got {:?}", r.err()),
+    }
+    assert!(CSR::new(2, vec![(0, 1)]).write_metis(out.clone()).is_err());
+    /*A one-way edge listed twice is matched by has_edge, but not edge for edge*/
+    match CSR::new(2, vec![(0, 1), (0, 1), (1, 0)]).write_metis(out.clone()) {
+        Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::InvalidInput => (),
+        r => panic!("expected an InvalidInput error, got {:?}", r.err()),
+    }
+    assert!(CSR::new(2, vec![(0, 1), (0, 1), (1, 0), (1, 0)]).write_metis(out.clone()).is_ok());
+    assert_eq!(fs::read_to_string(&out).unwrap(), "2 2\n2 2\n1 1\n");
+    assert!(CSR::new(2, vec![(0, 1), (1, 0)]).write_metis(out.clone()).is_ok());
+    assert_eq!(fs::read_to_string(&out).unwrap(), "2 1\n2\n1\n");
+
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(&out);
+}
+
+#[test]
+fn test_dimacs() {
+    let path = write_tmp(
+        "raphy_dimacs.gr",
+        "c 9th DIMACS Implementation Challenge: Shortest Paths\n\
+         c\n\
+         p sp 4 5\n\
+         a 1 2 803\n\
+         a 2 1 803\n\
+         \n\
+         a 1 4 158\n\
+         a 4 3 774\n\
+         a 3 3 1\n",
+    );
+    let g = CSR::<f64, i64>::new_weighted_from_dimacs(&path, &sorted()).unwrap();
+    assert_eq!((g.get_v(), g.get_e()), (4, 5));
+    assert_eq!(g.weighted_neighbors(0), (&[1, 3][..], &[803, 158][..]));
+    assert_eq!(g.weighted_neighbors(2), (&[2][..], &[1][..]));
+
+    let out = write_tmp("raphy_dimacs_out.gr", "");
+    g.write_dimacs(out.clone()).unwrap();
+    let again = CSR::<f64, i64>::new_weighted_from_dimacs(&out, &sorted()).unwrap();
+    assert_eq!(again.get_neighbs(), g.get_neighbs());
+    assert_eq!(again.get_weights(), g.get_weights());
+
+    let u = CSR::new_from_dimacs(&path, &sorted()).unwrap();
+    assert_eq!(u.get_neighbs(), g.get_neighbs());
+    u.write_dimacs(out.clone()).unwrap();
+    assert!(fs::read_to_string(&out).unwrap().starts_with("p sp 4 5\na 1 2 1\na 1 4 1\n"));
+
+    let cases = [
+        ("a 1 2 3\np sp 2 1\n", 1),
+        ("p sp 2 1\na 1 0 3\n", 2),
+        ("p sp 2 2\na 1 2 3\n", 2),
+        ("p sp 2 1\nx 1 2\n", 2),
+        ("p max 2 1\na 1 2 3\n", 1),
+        ("p sp 2 1\na 1 2 heavy\n", 2),
+    ];
+    for (i, (text, line)) in cases.iter().enumerate() {
+        fs::write(&path, text).unwrap();
+        match dimacs::read_wel::<u64>(&path) {
+            Err(Error::Parse { line: l, .. }) if l == *line => (),
+            r => panic!("case {}: expected a parse error on line {}, got {:?}", i, line, r.err()),
+        }
+    }
+    fs::write(&path, "p sp 2 1\na 1 3 3\n").unwrap();
+    match dimacs::read_el(&path) {
+        Err(Error::VertexOutOfRange { vtx: 2, v: 2 }) => (),
+        r => panic!("expected VertexOutOfRange, got {:?}", r.err()),
+    }
+
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(&out);
+}
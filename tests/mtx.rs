@@ -0,0 +1,132 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+mod common;
+use common::write_tmp;
+use raphy::csr::{BuildOptions, CSR};
+use raphy::mtx::{self, Field, Symmetry};
+use raphy::Error;
+use std::fs;
+
+#[test]
+fn test_mtx_read_write() {
+    /*A symmetric real matrix as SuiteSparse distributes them: lower
+    triangle only, with comments and a diagonal entry*/
+    let path = write_tmp(
+        "raphy_mtx_sym.mtx",
+        "%%MatrixMarket matrix coordinate real symmetric\n\
+         % a comment\n\
+         %\n\
+         4 4 4\n\
+         1 1 2.5\n\
+         2 1 -1\n\
+         4 2 0.125\n\
+         \n\
+         4 3 1e3\n",
+    );
+    let (h, el) = mtx::read_wel::<f64>(&path).unwrap();
+    assert_eq!(
+        (h.rows, h.cols, h.entries, h.field, h.symmetry),
+        (4, 4, 4, Field::Real, Symmetry::Symmetric)
+    );
+    assert_eq!(el.len(), 7);
+
+    let opts = BuildOptions {
+        sort: true,
+        ..Default::default()
+    };
+    let g = CSR::<f64, f64>::new_weighted_from_mtx(&path, &opts).unwrap();
+    assert_eq!((g.get_v(), g.get_e()), (4, 7));
+    assert_eq!(g.weighted_neighbors(0), (&[0, 1][..], &[2.5, -1.0][..]));
+    assert_eq!(g.weighted_neighbors(1), (&[0, 3][..], &[-1.0, 0.125][..]));
+    assert_eq!(g.weighted_neighbors(3), (&[1, 2][..], &[0.125, 1000.0][..]));
+
+    /*The same file read as a pattern, and written back out*/
+    let p = CSR::new_from_mtx(&path, &opts).unwrap();
+    assert_eq!(p.get_neighbs(), g.get_neighbs());
+    assert!(!p.is_weighted());
+
+    let out = std::env::temp_dir().join("raphy_mtx_out.mtx");
+    let out = out.to_str().unwrap().to_string();
+    g.write_mtx(out.clone()).unwrap();
+    let text = fs::read_to_string(&out).unwrap();
+    assert!(text.starts_with("%%MatrixMarket matrix coordinate real general\n4 4 7\n1 1 2.5\n"));
+    let again = CSR::<f64, f64>::new_weighted_from_mtx(&out, &opts).unwrap();
+    assert_eq!(again.get_neighbs(), g.get_neighbs());
+    assert_eq!(again.get_weights(), g.get_weights());
+
+    p.write_mtx(out.clone()).unwrap();
+    let (h, el) = mtx::read_el(&out).unwrap();
+    assert_eq!((h.field, h.symmetry, h.entries), (Field::Pattern, Symmetry::General, 7));
+    assert_eq!(el.len(), 7);
+
+    /*Integer weights are written as an integer matrix, and a non-square
+    matrix has as many vertices as its larger dimension*/
+    let w = CSR::<f64, u32>::new_weighted(3, vec![(0, 2, 7), (2, 1, 9)]);
+    w.write_mtx(out.clone()).unwrap();
+    let text = fs::read_to_string(&out).unwrap();
+    assert_eq!(text, "%%MatrixMarket matrix coordinate integer general\n3 3 2\n1 3 7\n3 2 9\n");
+    let rect = write_tmp(
+        "raphy_mtx_rect.mtx",
+        "%%MatrixMarket matrix coordinate integer general\n2 5 1\n2 5 3\n",
+    );
+    let r = CSR::<f64, i64>::new_weighted_from_mtx(&rect, &BuildOptions::default()).unwrap();
+    assert_eq!((r.get_v(), r.weighted_neighbors(1)), (5, (&[4][..], &[3i64][..])));
+
+    for p in [path, out, rect].iter() {
+        let _ = fs::remove_file(p);
+    }
+}
+
+#[test]
+fn test_mtx_errors() {
+    let cases = [
+        ("%%MatrixMarket matrix array real general\n2 2\n1\n2\n3\n4\n", 1),
+        ("%%MatrixMarket matrix coordinate complex general\n1 1 1\n1 1 1 0\n", 1),
+        ("%%MatrixMarket matrix coordinate pattern general\n2 2\n", 2),
+        ("%%MatrixMarket matrix coordinate pattern general\n2 2 1\n0 1\n", 3),
+        ("%%MatrixMarket matrix coordinate pattern general\n2 2 1\n1 2 3\n", 3),
+        ("%%MatrixMarket matrix coordinate pattern general\n2 2 2\n% c\n1 2\n", 4),
+        ("%%MatrixMarket matrix coordinate real general\n2 2 1\n1 2 x\n", 3),
+    ];
+    for (i, (text, line)) in cases.iter().enumerate() {
+        let path = write_tmp(&format!("raphy_mtx_err{}.mtx", i), text);
+        let r = match text.contains(" pattern ") {
+            true => mtx::read_el(&path).map(|_| ()),
+            false => mtx::read_wel::<f64>(&path).map(|_| ()),
+        };
+        match r {
+            Err(Error::Parse { line: l, .. }) if l == *line => (),
+            r => panic!("case {}: expected a parse error on line {}, got {:?}", i, line, r),
+        }
+        let _ = fs::remove_file(&path);
+    }
+
+    let path = write_tmp(
+        "raphy_mtx_range.mtx",
+        "%%MatrixMarket matrix coordinate pattern general\n2 2 1\n1 3\n",
+    );
+    match CSR::new_from_mtx(&path, &BuildOptions::default()) {
+        Err(Error::VertexOutOfRange { vtx: 2, v: 2 }) => (),
+        r => panic!("expected VertexOutOfRange, got {:?}", r.err()),
+    }
+    /*A pattern file has no weights*/
+    fs::write(&path, "%%MatrixMarket matrix coordinate pattern general\n2 2 1\n1 2\n").unwrap();
+    assert!(CSR::new_from_mtx(&path, &BuildOptions::default()).is_ok());
+    match CSR::<f64, f64>::new_weighted_from_mtx(&path, &BuildOptions::default()) {
+        Err(Error::Parse { line: 1, .. }) => (),
+        r => panic!("expected a parse error, got {:?}", r.err()),
+    }
+    let _ = fs::remove_file(&path);
+}
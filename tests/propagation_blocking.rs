@@ -0,0 +1,64 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::{BuildOptions, CSR};
+
+#[test]
+fn test_propagation_blocking() {
+    /*Enough vertices for several blocks*/
+    const NUMV: usize = 50000;
+    let el: Vec<(usize, usize, u32)> = CSR::random_el(NUMV, 8)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (v0, v1))| (v0, v1, i as u32))
+        .collect();
+
+    let det = BuildOptions {
+        deterministic: true,
+        ..Default::default()
+    };
+    let blocked = BuildOptions {
+        propagation_blocking: true,
+        ..Default::default()
+    };
+
+    /*Blocked placement keeps edge list order, so it matches a deterministic build*/
+    let a = CSR::new_weighted_with(NUMV, el.clone(), &det);
+    let b = CSR::new_weighted_with(NUMV, el.clone(), &blocked);
+    assert_eq!(a.get_offsets(), b.get_offsets());
+    assert_eq!(a.get_neighbs(), b.get_neighbs());
+    assert_eq!(a.get_weights(), b.get_weights());
+
+    let simple = BuildOptions {
+        symmetrize: true,
+        remove_self_loops: true,
+        dedup: true,
+        ..Default::default()
+    };
+    let a = CSR::new_weighted_with(NUMV, el.clone(), &simple);
+    let b = CSR::new_weighted_with(
+        NUMV,
+        el,
+        &BuildOptions {
+            propagation_blocking: true,
+            ..simple
+        },
+    );
+    assert_eq!(a.get_offsets(), b.get_offsets());
+    assert_eq!(a.get_neighbs(), b.get_neighbs());
+    assert_eq!(a.get_weights(), b.get_weights());
+
+    let empty = CSR::new_with(0, Vec::new(), &blocked);
+    assert_eq!((empty.get_v(), empty.get_e()), (0, 0));
+}
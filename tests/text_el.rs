@@ -0,0 +1,109 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::{BuildOptions, CSR};
+use raphy::text_el::{self, TextFormat};
+use raphy::Error;
+use std::fmt::Write;
+use std::fs;
+
+#[test]
+fn test_text_el_formats() {
+    let dir = std::env::temp_dir();
+    let snap = dir.join("raphy_text_snap.txt");
+    fs::write(
+        &snap,
+        "# Directed graph (each unordered pair of nodes is saved once)\n\
+         # FromNodeId\tToNodeId\n\
+         0\t1\n\
+         0\t4\r\n\
+         \n  # an indented comment\n\
+         4 2\n",
+    )
+    .unwrap();
+    let (v, el) = text_el::read_el(&snap, &TextFormat::snap()).unwrap();
+    assert_eq!((v, el), (5, vec![(0, 1), (0, 4), (4, 2)]));
+
+    /*A csv file with a header row, 1-based ids, and the weight in the
+    fourth column*/
+    let csv = dir.join("raphy_text_weighted.csv");
+    fs::write(&csv, "src, dst, label, w\n1, 2, a, 0.5\n3, 1, b, 2\n").unwrap();
+    let fmt = TextFormat {
+        skip_lines: 1,
+        weight_column: Some(3),
+        one_based: true,
+        ..TextFormat::csv()
+    };
+    let (v, el) = text_el::read_wel::<f64, _>(&csv, &fmt).unwrap();
+    assert_eq!((v, el), (3, vec![(0, 1, 0.5), (2, 0, 2.0)]));
+    let g = CSR::<f64, f64>::new_weighted_from_text(&csv, &fmt, &BuildOptions::default()).unwrap();
+    assert_eq!(g.weighted_neighbors(2), (&[0][..], &[2.0][..]));
+
+    /*Without the header skip, the header is a bad line*/
+    let fmt = TextFormat {
+        skip_lines: 0,
+        ..fmt
+    };
+    match text_el::read_el(&csv, &fmt) {
+        Err(Error::Parse { line: 1, .. }) => (),
+        r => panic!("expected a parse error on line 1, got {:?}", r.err()),
+    }
+
+    let _ = fs::remove_file(&snap);
+    let _ = fs::remove_file(&csv);
+}
+
+#[test]
+fn test_text_el_chunks() {
+    /*Big enough to be parsed in several chunks*/
+    const NUMV: usize = 100_000;
+    let el = CSR::random_el(NUMV, 8);
+    let mut text = String::from("# a large graph\n");
+    for (i, (v0, v1)) in el.iter().enumerate() {
+        if i % 1000 == 0 {
+            text.push_str("# checkpoint\n");
+        }
+        writeln!(text, "{}\t{}", v0, v1).unwrap();
+    }
+    assert!(text.len() > 3 << 20);
+    let path = std::env::temp_dir().join("raphy_text_chunks.txt");
+    fs::write(&path, &text).unwrap();
+
+    let (v, got) = text_el::read_el(&path, &TextFormat::snap()).unwrap();
+    assert_eq!(got, el);
+    assert_eq!(v, el.iter().map(|(a, b)| a.max(b)).max().unwrap() + 1);
+
+    let opts = BuildOptions {
+        sort: true,
+        ..Default::default()
+    };
+    let g = CSR::new_from_text(&path, &TextFormat::snap(), &opts).unwrap();
+    let want = CSR::new_with(v, el.clone(), &opts);
+    assert_eq!(g.get_neighbs(), want.get_neighbs());
+
+    /*A bad line deep in the file is reported with its own line number*/
+    let bad = text.lines().count() - 10;
+    let mut lines: Vec<&str> = text.lines().collect();
+    lines[bad - 1] = "12 x";
+    fs::write(&path, lines.join("\n")).unwrap();
+    match text_el::read_el(&path, &TextFormat::snap()) {
+        Err(Error::Parse { line, msg }) => {
+            assert_eq!(line as usize, bad);
+            assert!(msg.contains("\"x\""));
+        }
+        r => panic!("expected a parse error, got {:?}", r.err()),
+    }
+
+    let _ = fs::remove_file(&path);
+}
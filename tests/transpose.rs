@@ -0,0 +1,103 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::CSR;
+use raphy::fast_csr::FastCSR;
+use std::fs;
+
+/*Every edge of g as a sorted list of (v0,v1,w) triples*/
+fn edges(g: &CSR<f64, u32>) -> Vec<(usize, usize, u32)> {
+    let mut el = Vec::new();
+    g.read_only_scan_weighted(|v0, v1, w| el.push((v0, v1, w)));
+    el.sort_unstable();
+    el
+}
+
+#[test]
+fn test_csr_transpose() {
+    const NUMV: usize = 400;
+    let el: Vec<(usize, usize, u32)> = CSR::random_el(NUMV, 12)
+        .into_iter()
+        .map(|(v0, v1)| (v0, v1, (v0 * 3 + v1) as u32))
+        .collect();
+    let csr = CSR::new_weighted(NUMV, el);
+
+    let t = csr.transpose();
+    assert_eq!(t.get_e(), csr.get_e());
+    let mut flipped: Vec<_> = edges(&csr).into_iter().map(|(a, b, w)| (b, a, w)).collect();
+    flipped.sort_unstable();
+    assert_eq!(edges(&t), flipped);
+    assert_eq!(edges(&t.transpose()), edges(&csr));
+
+    let csr = csr.with_in_edges();
+    assert!(csr.has_in_edges());
+    for v in 0..NUMV {
+        assert_eq!(csr.in_degree(v), t.out_degree(v));
+        let mut ins = csr.in_neighbors(v).to_vec();
+        let mut outs = t.weighted_neighbors(v).0.to_vec();
+        ins.sort_unstable();
+        outs.sort_unstable();
+        assert_eq!(ins, outs);
+        let (nbs, wts) = csr.weighted_in_neighbors(v);
+        for (n, w) in nbs.iter().zip(wts) {
+            assert_eq!(*w, (n * 3 + v) as u32);
+        }
+    }
+
+    /*Transposing a graph that keeps its in-edges swaps the two directions*/
+    let tt = csr.transpose();
+    assert!(tt.has_in_edges());
+    assert_eq!(edges(&tt), edges(&t));
+    for v in 0..NUMV {
+        assert_eq!(tt.in_neighbors(v), csr.weighted_neighbors(v).0);
+    }
+}
+
+#[test]
+fn test_fastcsr_in_edges() {
+    const NUMV: usize = 300;
+    let csr = CSR::new(NUMV, CSR::random_el(NUMV, 10)).with_in_edges();
+
+    let path = std::env::temp_dir().join("raphy_in_edges.csr");
+    csr.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let fcsr = FastCSR::open(&path).unwrap();
+    let _ = fs::remove_file(&path);
+
+    assert!(fcsr.has_in_edges());
+    let mut indeg = vec![0usize; NUMV];
+    fcsr.in_neighbor_scan_prop(|_, nei| nei.len(), &mut indeg);
+    for (v, d) in indeg.iter().enumerate() {
+        let (st, en) = csr.vtx_offset_range(v);
+        assert_eq!(fcsr.neighbors(v), &csr.get_neighbs()[st..en]);
+        assert_eq!(fcsr.in_neighbors(v), csr.in_neighbors(v));
+        assert_eq!(fcsr.in_degree(v), *d);
+        assert_eq!(fcsr.out_degree(v), csr.out_degree(v));
+    }
+
+    /*A weighted graph with 4-byte weights still gets aligned in-edge sections*/
+    let wcsr = CSR::new_weighted(3, vec![(0, 1, 5u32), (2, 1, 7), (1, 0, 9)]).with_in_edges();
+    wcsr.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let fcsr = FastCSR::open(&path).unwrap();
+    let _ = fs::remove_file(&path);
+    let (nbs, wts) = fcsr.weighted_in_neighbors::<u32>(1);
+    let mut ins: Vec<_> = nbs.iter().cloned().zip(wts.iter().cloned()).collect();
+    ins.sort_unstable();
+    assert_eq!(ins, vec![(0, 5), (2, 7)]);
+
+    let plain = CSR::new(2, vec![(0, 1)]);
+    plain.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let fcsr = FastCSR::open(&path).unwrap();
+    let _ = fs::remove_file(&path);
+    assert!(!fcsr.has_in_edges());
+}
@@ -0,0 +1,71 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::CSR;
+use raphy::fast_csr::FastCSR;
+use std::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Deg {
+    out: usize,
+    smallest: usize,
+}
+
+#[test]
+fn test_generic_vtxprop() {
+    const NUMV: usize = 200;
+    let csr = CSR::new(NUMV, CSR::random_el(NUMV, 8));
+
+    let mut depths = csr.with_vtxprop(u32::MAX);
+    assert!(depths.get_vtxprop().iter().all(|d| *d == u32::MAX));
+    depths.par_scan(4, |_, nei| nei.len() as u32);
+    for v in 0..NUMV {
+        let (st, en) = depths.vtx_offset_range(v);
+        assert_eq!(depths.get_vtxprop()[v], (en - st) as u32);
+    }
+
+    let mut degs = depths.with_vtxprop(Deg {
+        out: 0,
+        smallest: 0,
+    });
+    degs.par_scan(16, |v, nei| Deg {
+        out: nei.len(),
+        smallest: nei.iter().cloned().min().unwrap_or(v),
+    });
+    degs.get_mut_vtxprop()[0].out += 1;
+    let (st, en) = degs.vtx_offset_range(0);
+    assert_eq!(degs.get_vtxprop()[0].out, en - st + 1);
+
+    /*The default property type is still f64*/
+    let csr = CSR::new(2, vec![(0, 1)]);
+    let zero: f64 = csr.get_vtxprop()[0];
+    assert_eq!(zero, 0.0);
+}
+
+#[test]
+fn test_fastcsr_generic_prop() {
+    const NUMV: usize = 100;
+    let csr = CSR::new(NUMV, CSR::random_el(NUMV, 8));
+    let path = std::env::temp_dir().join("raphy_vtxprop.csr");
+    csr.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let fcsr = FastCSR::open(&path).unwrap();
+    let _ = fs::remove_file(&path);
+
+    let mut labels = vec![0usize; NUMV];
+    fcsr.neighbor_scan_prop(|v, nei| nei.iter().cloned().fold(v, usize::min), &mut labels);
+    for (v, l) in labels.iter().enumerate() {
+        assert!(*l <= v);
+        assert!(fcsr.neighbors(v).iter().all(|n| *l <= *n));
+    }
+}
@@ -0,0 +1,130 @@
+/*
+Copyright 2020 Brandon Lucia <blucia@gmail.com>
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+extern crate raphy;
+use raphy::csr::CSR;
+use raphy::error::FormatError;
+use raphy::fast_csr::FastCSR;
+use raphy::Error;
+use std::fs;
+
+/*Weight each edge of a random graph by a function of its endpoints so
+that the weights can be checked no matter how neighbors get ordered*/
+fn weighted_el(numv: usize) -> Vec<(usize, usize, u32)> {
+    CSR::random_el(numv, 10)
+        .into_iter()
+        .map(|(v0, v1)| (v0, v1, (v0 * 7 + v1) as u32))
+        .collect()
+}
+
+#[test]
+fn test_weighted_csr() {
+    const NUMV: usize = 500;
+    let el = weighted_el(NUMV);
+    let nume = el.len();
+    let mut csr = CSR::new_weighted(NUMV, el);
+
+    assert!(csr.is_weighted());
+    assert_eq!(csr.get_weights().unwrap().len(), nume);
+
+    let mut cnt = 0;
+    csr.read_only_scan_weighted(|v0, v1, w| {
+        assert_eq!(w, (v0 * 7 + v1) as u32);
+        cnt += 1;
+    });
+    assert_eq!(cnt, nume);
+
+    csr.par_scan_weighted(|_, _, wts| wts.iter().map(|w| *w as f64).sum());
+    for v in 0..NUMV {
+        let (nbs, _) = csr.weighted_neighbors(v);
+        let expect: f64 = nbs.iter().map(|n| (v * 7 + n) as f64).sum();
+        assert_eq!(csr.get_vtxprop()[v], expect);
+    }
+
+    assert!(!CSR::new(2, vec![(0, 1)]).is_weighted());
+}
+
+#[test]
+fn test_weighted_loaders() {
+    let path = std::env::temp_dir().join("raphy_weighted.el");
+    let name = path.to_str().unwrap().to_string();
+
+    fs::write(&path, "0,1,0.5\n1,2,1.5\n2,0,-2\n").unwrap();
+    let (numv, el) = CSR::<f64>::wel_from_file(&name).unwrap();
+    assert_eq!(numv, 3);
+    assert_eq!(el, vec![(0, 1, 0.5), (1, 2, 1.5), (2, 0, -2.0)]);
+
+    fs::write(&path, "0,1,3\n1,2,x\n").unwrap();
+    match CSR::<f64, u64>::wel_from_file(&name) {
+        Err(Error::Parse { line: 2, .. }) => (),
+        r => panic!("expected a parse error on line 2, got {:?}", r.err()),
+    }
+
+    /*Binary triples, with f64 weights stored as their bits*/
+    let bytes: Vec<u8> = [(0, 1, 0.5f64), (1, 2, 1.5), (2, 0, -2.0)]
+        .iter()
+        .flat_map(|&(v0, v1, w): &(usize, usize, f64)| {
+            [v0 as u64, v1 as u64, w.to_bits()]
+                .iter()
+                .flat_map(|x| x.to_ne_bytes().to_vec())
+                .collect::<Vec<u8>>()
+        })
+        .collect();
+    fs::write(&path, &bytes).unwrap();
+    let csr = CSR::<f64>::new_from_wel_mmap(3, name.clone()).unwrap();
+    assert_eq!(csr.weighted_neighbors(1), (&[2usize][..], &[1.5][..]));
+    assert_eq!(csr.weighted_neighbors(2), (&[0usize][..], &[-2.0][..]));
+
+    fs::write(&path, &bytes[..16]).unwrap();
+    match CSR::<f64>::new_from_wel_mmap(3, name) {
+        Err(Error::Format(FormatError::EdgeListLength(16))) => (),
+        r => panic!("expected an edge list length error, got {:?}", r.err()),
+    }
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn test_weighted_fastcsr() {
+    const NUMV: usize = 300;
+    let csr = CSR::new_weighted(NUMV, weighted_el(NUMV));
+
+    let path = std::env::temp_dir().join("raphy_weighted.csr");
+    csr.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let fcsr = FastCSR::open(&path).unwrap();
+    let _ = fs::remove_file(&path);
+
+    assert!(fcsr.is_weighted());
+    assert!(fcsr.check_weights::<u32>().is_ok());
+    match fcsr.check_weights::<f64>() {
+        Err(Error::Format(FormatError::WeightKind { .. })) => (),
+        r => panic!("expected a weight kind error, got {:?}", r),
+    }
+
+    for v in 0..NUMV {
+        assert_eq!(fcsr.weighted_neighbors::<u32>(v), csr.weighted_neighbors(v));
+    }
+
+    let mut prop = vec![0.0; NUMV];
+    fcsr.neighbor_scan_prop_weighted(|_, _, wts: &[u32]| wts.len() as f64, &mut prop);
+    for (v, p) in prop.iter().enumerate() {
+        assert_eq!(*p, fcsr.neighbors(v).len() as f64);
+    }
+
+    let unweighted = CSR::new(2, vec![(0, 1)]);
+    unweighted.write_fastcsr(path.to_str().unwrap().to_string()).unwrap();
+    let fcsr = FastCSR::open(&path).unwrap();
+    let _ = fs::remove_file(&path);
+    assert!(!fcsr.is_weighted());
+    assert!(fcsr.check_weights::<u32>().is_err());
+}